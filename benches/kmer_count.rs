@@ -103,6 +103,30 @@ where
     counter
 }
 
+/* in_place_fastx fold parser */
+fn in_place_fastx_kmer_count_fold<P>(path: P, block_length: u64) -> Counter<u64, KMER_SPACE>
+where
+    P: AsRef<std::path::Path>,
+{
+    in_place_fastx::parser::fold::fold(
+        in_place_fastx::fastq::Producer::with_blocksize(block_length, path).unwrap(),
+        in_place_fastx::fastq::Reader::read_record,
+        || -> Counter<u64, KMER_SPACE> { Counter::new() },
+        |counter: &mut Counter<u64, KMER_SPACE>, record: in_place_fastx::block::Record| {
+            for kmer in cocktail::tokenizer::Tokenizer::new(record.sequence, K as u8) {
+                counter[kmer as usize] += 1;
+            }
+        },
+        |mut a, b| {
+            for (x, y) in a.iter_mut().zip(b.iter()) {
+                *x += y;
+            }
+            a
+        },
+    )
+    .unwrap()
+}
+
 /* rust bio parser */
 fn bio_kmer_count<P>(path: P, capacity: u64) -> Counter<u64, KMER_SPACE>
 where
@@ -172,6 +196,13 @@ fn blocksize(c: &mut criterion::Criterion) {
                 b.iter(|| criterion::black_box(in_place_fastx_kmer_count_parallel(&file, length)))
             },
         );
+        g.bench_with_input(
+            criterion::BenchmarkId::new("in_place_fastx_fold", 2_u64.pow(power2)),
+            &2_u64.pow(power2),
+            |b, &length| {
+                b.iter(|| criterion::black_box(in_place_fastx_kmer_count_fold(&file, length)))
+            },
+        );
         g.bench_with_input(
             criterion::BenchmarkId::new("bio", 2_u64.pow(power2)),
             &2_u64.pow(power2),
@@ -185,10 +216,56 @@ fn blocksize(c: &mut criterion::Criterion) {
     }
 }
 
+/* new line search, from end of block, used to find last record of block */
+fn bstr_last_newlines(block: &[u8], nb: usize) -> usize {
+    use bstr::ByteSlice;
+
+    let mut end = block.len();
+    for _ in 0..nb {
+        end = block[..end].rfind_byte(b'\n').unwrap();
+    }
+
+    end
+}
+
+fn memchr_last_newlines(block: &[u8], nb: usize) -> usize {
+    let mut newlines = memchr::memrchr_iter(b'\n', block);
+
+    let mut end = block.len();
+    for _ in 0..nb {
+        end = newlines.next().unwrap();
+    }
+
+    end
+}
+
+fn newline(c: &mut criterion::Criterion) {
+    let file = generate_fastq(42, 100_000, 150);
+    let data = std::fs::read(file.path()).unwrap();
+
+    let mut g = c.benchmark_group("newline");
+
+    for power2 in 11..24 {
+        let block = &data[..2_usize.pow(power2)];
+
+        g.bench_with_input(
+            criterion::BenchmarkId::new("bstr", 2_u64.pow(power2)),
+            block,
+            |b, block| b.iter(|| criterion::black_box(bstr_last_newlines(block, 5))),
+        );
+        g.bench_with_input(
+            criterion::BenchmarkId::new("memchr", 2_u64.pow(power2)),
+            block,
+            |b, block| b.iter(|| criterion::black_box(memchr_last_newlines(block, 5))),
+        );
+    }
+}
+
 fn setup(c: &mut criterion::Criterion) {
     let _ = env_logger::builder().is_test(true).try_init();
 
     blocksize(c);
+    newline(c);
 }
 
 criterion::criterion_group!(benches, setup);
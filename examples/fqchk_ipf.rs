@@ -4,45 +4,76 @@ use clap::Parser;
 mod fqchk_utils;
 use fqchk_utils::{Command, Data, PosInfo};
 
-in_place_fastx::fastq_sequential!(
-    Sequential,
-    Data,
-    |record: in_place_fastx::block::Record, data: &mut Data| {
-        // Update min and max length
-        if data.min_len > record.quality.len() as u64 {
-            data.min_len = record.quality.len() as u64;
-        }
-
-        if data.max_len < record.quality.len() as u64 {
-            data.max_len = record.quality.len() as u64;
-        }
-
-        data.seq_count += 1;
-        data.average_len = (1.0 / data.seq_count as f64) * record.sequence.len() as f64
-            + (1.0 - (1.0 / data.seq_count as f64)) * data.average_len as f64;
-
-        // Update count table length
-        if record.quality.len() > data.pos_infos.len() {
-            data.pos_infos.extend(vec![
-                PosInfo::new(data.threshold);
-                record.quality.len() - data.pos_infos.len()
-            ]);
-        }
-
-        for (indice, (nuc, qual)) in record.sequence.iter().zip(record.quality).enumerate() {
-            data.pos_infos[indice].quality_counts[(qual - data.phred_offset) as usize] += 1;
-            data.pos_infos[indice].bases_count[(nuc >> 1 & 0b111) as usize] += 1;
-        }
-    }
-);
+fn update(record: in_place_fastx::block::Record, data: &mut Data) {
+    // Update min and max length
+    if data.min_len > record.quality.len() as u64 {
+        data.min_len = record.quality.len() as u64;
+    }
+
+    if data.max_len < record.quality.len() as u64 {
+        data.max_len = record.quality.len() as u64;
+    }
+
+    data.seq_count += 1;
+    data.average_len = (1.0 / data.seq_count as f64) * record.sequence.len() as f64
+        + (1.0 - (1.0 / data.seq_count as f64)) * data.average_len as f64;
+
+    // Update count table length
+    if record.quality.len() > data.pos_infos.len() {
+        data.pos_infos.extend(vec![
+            PosInfo::new(data.threshold);
+            record.quality.len() - data.pos_infos.len()
+        ]);
+    }
+
+    for (indice, (nuc, qual)) in record
+        .sequence
+        .iter()
+        .zip(record.quality_scores(data.phred_offset))
+        .enumerate()
+    {
+        data.pos_infos[indice].quality_counts[qual as usize] += 1;
+        data.pos_infos[indice].bases_count[(nuc >> 1 & 0b111) as usize] += 1;
+    }
+}
+
+/// Merge data of two threads
+fn merge(mut data: Data, other: Data) -> Data {
+    let seq_count = data.seq_count + other.seq_count;
+    if seq_count != 0 {
+        data.average_len = (data.average_len * data.seq_count as f64
+            + other.average_len * other.seq_count as f64)
+            / seq_count as f64;
+    }
+    data.seq_count = seq_count;
+
+    data.min_len = data.min_len.min(other.min_len);
+    data.max_len = data.max_len.max(other.max_len);
+
+    if other.pos_infos.len() > data.pos_infos.len() {
+        data.pos_infos
+            .resize(other.pos_infos.len(), PosInfo::new(data.threshold));
+    }
+    for (pos, other) in data.pos_infos.iter_mut().zip(other.pos_infos.iter()) {
+        pos.add(other);
+    }
+
+    data
+}
 
 fn main() -> in_place_fastx::error::Result<()> {
     let params = Command::parse();
 
     let mut data = Data::new(params.qual_t);
-    let mut parser = Sequential::new();
     for input in params.inputs {
-        parser.with_blocksize(params.blocksize, input, &mut data)?;
+        let local = in_place_fastx::parser::fold::fold(
+            in_place_fastx::fastq::Producer::with_blocksize(params.blocksize, input)?,
+            in_place_fastx::fastq::Reader::read_record,
+            || Data::new(params.qual_t),
+            |data: &mut Data, record: in_place_fastx::block::Record| update(record, data),
+            merge,
+        )?;
+        data = merge(data, local);
     }
 
     println!("{}", data);
@@ -3,168 +3,1578 @@
 
 /* project use */
 
-/// Block reperesent a section of file memory mapped in file
+/// False if feature `no_mmap` is set, in this case producer read file instead of map it
+pub const USE_MMAP: bool = !cfg!(feature = "no_mmap");
+
+/// UTF-8 byte order mark, producer skip it if file begin by it
+pub const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// Memory that contains data of a [Block], shared between blocks
 #[derive(Debug)]
+pub enum Buffer {
+    Mmap(memmap2::Mmap),
+    Vec(Vec<u8>),
+}
+
+impl std::ops::Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Buffer::Mmap(mem) => mem,
+            Buffer::Vec(data) => data,
+        }
+    }
+}
+
+/// Block reperesent a section of file memory mapped in file, or read in memory if mapping isn't possible
+#[derive(Debug, Clone)]
 pub struct Block {
-    mem: memmap2::Mmap,
+    buffer: std::sync::Arc<Buffer>,
+    buffer_offset: u64,
+    begin: usize,
     end: usize,
+    evict_on_drop: bool,
+    crlf: bool,
 }
 
 impl Block {
     /// Create a new Block
     pub fn new(end: usize, mem: memmap2::Mmap) -> Self {
-        Self { mem, end }
+        Self::from_shared(std::sync::Arc::new(Buffer::Mmap(mem)), 0, end)
+    }
+
+    /// Create a new Block on section begin..end of a buffer that contains the whole file
+    pub fn from_shared(buffer: std::sync::Arc<Buffer>, begin: usize, end: usize) -> Self {
+        Self {
+            buffer,
+            buffer_offset: 0,
+            begin,
+            end,
+            evict_on_drop: false,
+            crlf: false,
+        }
+    }
+
+    /// Create a new Block from data read in file at file_offset
+    pub fn from_vec(file_offset: u64, data: Vec<u8>) -> Self {
+        Self {
+            begin: 0,
+            end: data.len(),
+            buffer: std::sync::Arc::new(Buffer::Vec(data)),
+            buffer_offset: file_offset,
+            evict_on_drop: false,
+            crlf: false,
+        }
+    }
+
+    /// Create a new Block with a copy of data, data must contains only complete records
+    pub fn from_slice(data: &[u8]) -> Self {
+        Self::from_vec(0, data.to_vec())
     }
 
     /// Acces to data owned by block
     pub fn data(&self) -> &[u8] {
-        &self.mem[..self.end]
+        &self.buffer[self.begin..self.end]
     }
 
     /// Get length of block
     pub fn len(&self) -> usize {
-        self.mem[..self.end].len()
+        self.end - self.begin
+    }
+
+    /// Get offset of block begin in file
+    pub fn file_offset(&self) -> u64 {
+        self.buffer_offset + self.begin as u64
     }
 
     /// Return true if the block is empty
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Shorten the block, keep only the first `len` bytes
+    pub fn truncate(&mut self, len: usize) {
+        self.end = self.end.min(self.begin + len);
+    }
+
+    /// If true, when block is drop kernel is advise to drop pages of file mapping it covers, only available on unix
+    ///
+    /// Pages are read again from file if they are access later, by a clone of block or a block on same section, so it only cost time. It's a no-op if block isn't map on memory.
+    pub fn set_evict_on_drop(&mut self, value: bool) {
+        self.evict_on_drop = value;
+    }
+
+    /// If true lines of block end by `\r\n`, readers remove `\r` at end of record lines
+    pub fn set_crlf(&mut self, value: bool) {
+        self.crlf = value;
+    }
+
+    /// True if lines of block end by `\r\n`, see [set_crlf](Block::set_crlf)
+    pub fn is_crlf(&self) -> bool {
+        self.crlf
+    }
+
+    /// Get data as a str, fails if data isn't valid UTF-8, fasta and fastq are ASCII so it's usually valid
+    pub fn try_as_str(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(self.data())
+    }
+
+    /// Iterate over fasta records of block, iteration stop after first error
+    ///
+    /// Records aren't validated, use [fasta::Reader](crate::fasta::Reader) to control validation.
+    pub fn records_fasta(&self) -> impl Iterator<Item = crate::error::Result<Record<'_>>> + '_ {
+        self.records_with(crate::fasta::Reader::read_record)
+    }
+
+    /// Iterate over fastq records of block, iteration stop after first error
+    ///
+    /// Records aren't validated, use [fastq::Reader](crate::fastq::Reader) to control validation.
+    pub fn records_fastq(&self) -> impl Iterator<Item = crate::error::Result<Record<'_>>> + '_ {
+        self.records_with(crate::fastq::Reader::read_record)
+    }
+
+    fn records_with<'a>(
+        &'a self,
+        read_record: fn(&'a Block, &mut usize) -> crate::error::Result<Option<Record<'a>>>,
+    ) -> impl Iterator<Item = crate::error::Result<Record<'a>>> + 'a {
+        let mut offset = 0;
+        let mut failed = false;
+
+        std::iter::from_fn(move || {
+            if failed {
+                return None;
+            }
+
+            match read_record(self, &mut offset) {
+                Ok(record) => record.map(Ok),
+                Err(e) => {
+                    failed = true;
+                    Some(Err(e))
+                }
+            }
+        })
+    }
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if self.evict_on_drop && !self.is_empty() {
+            if let Buffer::Mmap(mem) = &*self.buffer {
+                // it's only a hint, error is ignored
+                let _ = mem.advise_range(memmap2::Advice::DontNeed, self.begin, self.len());
+            }
+        }
+    }
 }
 
+/// Index of each byte in result of [Record::base_counts], A: 0, C: 1, G: 2, T: 3, other: 4
+pub(crate) const BASE_INDEX: [u8; 256] = {
+    let mut table = [4; 256];
+
+    table[b'A' as usize] = 0;
+    table[b'a' as usize] = 0;
+    table[b'C' as usize] = 1;
+    table[b'c' as usize] = 1;
+    table[b'G' as usize] = 2;
+    table[b'g' as usize] = 2;
+    table[b'T' as usize] = 3;
+    table[b't' as usize] = 3;
+
+    table
+};
+
+/// Composition of a sequence, see [Record::composition]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Composition {
+    /// Number of A, case insensitive
+    pub a: u64,
+    /// Number of C, case insensitive
+    pub c: u64,
+    /// Number of G, case insensitive
+    pub g: u64,
+    /// Number of T, case insensitive
+    pub t: u64,
+    /// Number of N, case insensitive
+    pub n: u64,
+    /// Number of soft-masked bases, lowercase a, c, g and t, they are also count in a, c, g and t
+    pub lower: u64,
+    /// Number of bytes that aren't A, C, G, T or N
+    pub other: u64,
+}
+
+/// A record read in a block, fields borrow data of block
+///
+/// Fasta and fastq records share this type, plus and quality of a fasta record are always empty, check it with [is_fasta](Record::is_fasta).
 pub struct Record<'a> {
+    /// Header line as found in file, with its sigil (`@` or `>`), see [name](Record::name)
     pub comment: &'a [u8],
     pub sequence: &'a [u8],
+    /// Separator line of a fastq record with its `+`, empty for a fasta record
     pub plus: &'a [u8],
+    /// Quality line of a fastq record, empty for a fasta record or a `*` placeholder, see [has_quality](Record::has_quality)
     pub quality: &'a [u8],
 }
 
+impl<'a> Record<'a> {
+    /// Remove a `\r` at end of each line of record, lines of a file with `\r\n` line ending keep it
+    pub fn trim_carriage_return(self) -> Self {
+        fn trim(line: &[u8]) -> &[u8] {
+            line.strip_suffix(b"\r").unwrap_or(line)
+        }
+
+        Record {
+            comment: trim(self.comment),
+            sequence: trim(self.sequence),
+            plus: trim(self.plus),
+            quality: trim(self.quality),
+        }
+    }
+
+    /// True if record have no plus line, as records read by [fasta::Reader](crate::fasta::Reader), a fastq plus line contains at least `+`
+    pub fn is_fasta(&self) -> bool {
+        self.plus.is_empty()
+    }
+
+    /// True if record have a quality, quality is empty for a fasta record or a fastq record with a `*` placeholder quality
+    pub fn has_quality(&self) -> bool {
+        !self.quality.is_empty()
+    }
+
+    /// Get header line without its sigil (`@` or `>`), comment is keep unchanged
+    pub fn name(&self) -> &'a [u8] {
+        self.comment.get(1..).unwrap_or(&[])
+    }
+
+    /// Get comment as a str, fails if comment isn't valid UTF-8
+    pub fn comment_str(&self) -> Result<&'a str, std::str::Utf8Error> {
+        std::str::from_utf8(self.comment)
+    }
+
+    /// Get sequence as a str, fails if sequence isn't valid UTF-8
+    pub fn sequence_str(&self) -> Result<&'a str, std::str::Utf8Error> {
+        std::str::from_utf8(self.sequence)
+    }
+
+    /// Get plus line as a str, fails if plus isn't valid UTF-8
+    pub fn plus_str(&self) -> Result<&'a str, std::str::Utf8Error> {
+        std::str::from_utf8(self.plus)
+    }
+
+    /// Get quality as a str, fails if quality isn't valid UTF-8
+    pub fn quality_str(&self) -> Result<&'a str, std::str::Utf8Error> {
+        std::str::from_utf8(self.quality)
+    }
+
+    /// Get identifier of record, bytes between sigil (`@` or `>`) and first ASCII whitespace (space, tab, ...)
+    pub fn id(&self) -> &'a [u8] {
+        let name = self.name();
+
+        match name.iter().position(|c| c.is_ascii_whitespace()) {
+            Some(end) => &name[..end],
+            None => name,
+        }
+    }
+
+    /// Get description of record, bytes after the first ASCII whitespace following identifier
+    pub fn description(&self) -> &'a [u8] {
+        let name = self.name();
+
+        match name.iter().position(|c| c.is_ascii_whitespace()) {
+            Some(end) => &name[end + 1..],
+            None => &name[name.len()..],
+        }
+    }
+
+    /// Count number of A, C, G, T (case insensitive) and other bytes in sequence
+    pub fn base_counts(&self) -> [u64; 5] {
+        let mut counts = [0; 5];
+
+        for nuc in self.sequence {
+            counts[BASE_INDEX[*nuc as usize] as usize] += 1;
+        }
+
+        counts
+    }
+
+    /// Count A, C, G, T, N, soft-masked and other bytes of sequence in one pass
+    pub fn composition(&self) -> Composition {
+        let mut composition = Composition::default();
+
+        for nuc in self.sequence {
+            match nuc {
+                b'A' => composition.a += 1,
+                b'C' => composition.c += 1,
+                b'G' => composition.g += 1,
+                b'T' => composition.t += 1,
+                b'a' => {
+                    composition.a += 1;
+                    composition.lower += 1;
+                }
+                b'c' => {
+                    composition.c += 1;
+                    composition.lower += 1;
+                }
+                b'g' => {
+                    composition.g += 1;
+                    composition.lower += 1;
+                }
+                b't' => {
+                    composition.t += 1;
+                    composition.lower += 1;
+                }
+                b'N' | b'n' => composition.n += 1,
+                _ => composition.other += 1,
+            }
+        }
+
+        composition
+    }
+
+    /// Count number of N, case insensitive, in sequence
+    pub fn count_n(&self) -> u64 {
+        self.sequence
+            .iter()
+            .filter(|nuc| **nuc == b'N' || **nuc == b'n')
+            .count() as u64
+    }
+
+    /// Count number of soft-masked bases, lowercase a, c, g and t, in sequence
+    pub fn count_softmasked(&self) -> u64 {
+        self.sequence
+            .iter()
+            .filter(|nuc| matches!(nuc, b'a' | b'c' | b'g' | b't'))
+            .count() as u64
+    }
+
+    /// Get ratio of G and C in A, C, G and T bases of sequence, 0 if sequence contains none of them
+    pub fn gc_content(&self) -> f64 {
+        let counts = self.base_counts();
+        let acgt = counts[0] + counts[1] + counts[2] + counts[3];
+
+        if acgt == 0 {
+            0.0
+        } else {
+            (counts[1] + counts[2]) as f64 / acgt as f64
+        }
+    }
+
+    /// Check each base of sequence is in allowed, return index of the first base not allowed
+    pub fn validate_alphabet(&self, allowed: &[u8]) -> Result<(), usize> {
+        let mut table = [false; 256];
+        for byte in allowed {
+            table[*byte as usize] = true;
+        }
+
+        match self.sequence.iter().position(|c| !table[*c as usize]) {
+            Some(pos) => Err(pos),
+            None => Ok(()),
+        }
+    }
+
+    /// Iterate over 2 bits encoded kmers of sequence, sequence shorter than k produce no kmer
+    #[cfg(feature = "cocktail")]
+    pub fn kmers(&self, k: u8) -> impl Iterator<Item = u64> + 'a {
+        let sequence = self.sequence;
+
+        (sequence.len() >= k as usize)
+            .then(|| cocktail::tokenizer::Tokenizer::new(sequence, k))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Iterate over 2 bits encoded canonical kmers of sequence, sequence shorter than k produce no kmer
+    #[cfg(feature = "cocktail")]
+    pub fn canonical_kmers(&self, k: u8) -> impl Iterator<Item = u64> + 'a {
+        let sequence = self.sequence;
+
+        (sequence.len() >= k as usize)
+            .then(|| cocktail::tokenizer::Canonical::new(sequence, k))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Get xxh3 hash of sequence, value is stable between run and platform
+    ///
+    /// Different sequences could have the same hash, a set of hash can't prove a sequence is a duplicate, with n sequences probability of a collision is around n²/2⁶⁵.
+    #[cfg(feature = "xxhash-rust")]
+    pub fn seq_hash(&self) -> u64 {
+        xxhash_rust::xxh3::xxh3_64(self.sequence)
+    }
+
+    /// Get xxh3 hash of comment, sequence, plus and quality, same collision caveat as [seq_hash](Record::seq_hash)
+    #[cfg(feature = "xxhash-rust")]
+    pub fn record_hash(&self) -> u64 {
+        let mut hasher = xxhash_rust::xxh3::Xxh3::new();
+
+        // fields can't contain a new line, it separate them without ambiguity
+        for field in [self.comment, self.sequence, self.plus, self.quality] {
+            hasher.update(field);
+            hasher.update(b"\n");
+        }
+
+        hasher.digest()
+    }
+
+    /// Get Phred score of each quality byte, byte lower than offset are saturate to 0
+    pub fn quality_scores(&self, offset: u8) -> impl Iterator<Item = u8> + 'a {
+        self.quality.iter().map(move |q| q.saturating_sub(offset))
+    }
+
+    /// Get Phred score of each quality byte, byte lower than offset produce an error
+    pub fn checked_quality_scores(
+        &self,
+        offset: u8,
+    ) -> impl Iterator<Item = crate::error::Result<u8>> + 'a {
+        self.quality.iter().map(move |q| {
+            q.checked_sub(offset)
+                .ok_or(crate::error::Error::QualityBelowOffset {
+                    quality: *q,
+                    phred_offset: offset,
+                })
+        })
+    }
+
+    /// Get error probability, 10^(-q/10), of each quality byte, byte lower than offset are saturate to 0
+    pub fn error_probabilities(&self, offset: u8) -> impl Iterator<Item = f64> + 'a {
+        self.quality_scores(offset)
+            .map(|q| 10_f64.powf(-(q as f64) / 10.0))
+    }
+
+    /// Get mean of Phred score of quality bytes, byte lower than offset are saturate to 0, 0 for an empty quality
+    pub fn mean_quality(&self, offset: u8) -> f64 {
+        if self.quality.is_empty() {
+            return 0.0;
+        }
+
+        self.quality_scores(offset).map(|q| q as u64).sum::<u64>() as f64
+            / self.quality.len() as f64
+    }
+
+    /// Get median of Phred score of quality bytes, lower median for an even number of bytes, 0 for an empty quality
+    ///
+    /// Byte lower than offset are saturate to 0 and score greater than [MAX_PHRED] to [MAX_PHRED], no allocation is done.
+    pub fn median_quality(&self, offset: u8) -> u8 {
+        if self.quality.is_empty() {
+            return 0;
+        }
+
+        let mut counts = [0usize; MAX_PHRED as usize + 1];
+        for q in self.quality_scores(offset) {
+            counts[q.min(MAX_PHRED) as usize] += 1;
+        }
+
+        let rank = (self.quality.len() - 1) / 2;
+        let mut seen = 0;
+        for (q, count) in counts.iter().enumerate() {
+            seen += count;
+            if seen > rank {
+                return q as u8;
+            }
+        }
+
+        MAX_PHRED
+    }
+
+    /// Copy sequence, bases with a quality lower than min_q are replace by mask, bases without quality are keep
+    pub fn masked_sequence(&self, min_q: u8, offset: u8, mask: u8) -> Vec<u8> {
+        self.sequence
+            .iter()
+            .enumerate()
+            .map(|(i, base)| match self.quality.get(i) {
+                Some(q) if q.saturating_sub(offset) < min_q => mask,
+                _ => *base,
+            })
+            .collect()
+    }
+
+    /// Pack sequence with two bits by base, A: 0, C: 1, T: 2, G: 3 (`nuc >> 1 & 0b11`), return packed bytes and number of bases
+    ///
+    /// First base is store in the two highest bits of first byte. Other bytes than ACGT are pack with same mapping, use [Record::non_acgt_mask] to find them, unpack with [unpack_2bit](crate::seq::unpack_2bit).
+    pub fn pack_2bit(&self) -> (Vec<u8>, usize) {
+        let mut packed = vec![0; (self.sequence.len() + 3) / 4];
+
+        for (i, nuc) in self.sequence.iter().enumerate() {
+            packed[i / 4] |= (nuc >> 1 & 0b11) << (6 - 2 * (i % 4));
+        }
+
+        (packed, self.sequence.len())
+    }
+
+    /// Bitset of bases that aren't A, C, G or T (in any case), first base is the lowest bit of first byte
+    pub fn non_acgt_mask(&self) -> Vec<u8> {
+        let mut mask = vec![0; (self.sequence.len() + 7) / 8];
+
+        for (i, nuc) in self.sequence.iter().enumerate() {
+            if BASE_INDEX[*nuc as usize] == 4 {
+                mask[i / 8] |= 1 << (i % 8);
+            }
+        }
+
+        mask
+    }
+
+    /// Copy data of record in an [OwnedRecord]
+    pub fn to_owned(&self) -> OwnedRecord {
+        OwnedRecord {
+            comment: self.comment.to_vec(),
+            sequence: self.sequence.to_vec(),
+            plus: self.plus.to_vec(),
+            quality: self.quality.to_vec(),
+        }
+    }
+}
+
+/// A record that own its data, it can outlive the [Block] it's read from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OwnedRecord {
+    pub comment: Vec<u8>,
+    pub sequence: Vec<u8>,
+    pub plus: Vec<u8>,
+    pub quality: Vec<u8>,
+}
+
+impl OwnedRecord {
+    /// Get a [Record] that borrow data of this record
+    pub fn as_record(&self) -> Record<'_> {
+        Record {
+            comment: &self.comment,
+            sequence: &self.sequence,
+            plus: &self.plus,
+            quality: &self.quality,
+        }
+    }
+}
+
+impl<'a> From<Record<'a>> for OwnedRecord {
+    fn from(record: Record<'a>) -> Self {
+        record.to_owned()
+    }
+}
+
+/// Largest Phred score that a printable quality byte encode with offset 33
+pub const MAX_PHRED: u8 = 93;
+
+/// Number of records a reader check before it use a fixed length fast path
+pub const UNIFORM_PROBE: usize = 64;
+
+/// Detect if sequences of first [UNIFORM_PROBE] records read have the same length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformLength {
+    length: Option<usize>,
+    nb_probed: usize,
+}
+
+impl UniformLength {
+    /// If enabled is false, length is never detected
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            length: None,
+            nb_probed: if enabled { 0 } else { UNIFORM_PROBE },
+        }
+    }
+
+    /// Get sequence length, None until [UNIFORM_PROBE] records with same length are read
+    #[inline(always)]
+    pub fn length(&self) -> Option<usize> {
+        if self.nb_probed == UNIFORM_PROBE {
+            self.length
+        } else {
+            None
+        }
+    }
+
+    /// Register sequence length of a record, do nothing after probe end
+    #[inline(always)]
+    pub fn update(&mut self, length: usize) {
+        if self.nb_probed == UNIFORM_PROBE {
+            return;
+        }
+
+        if self.nb_probed != 0 && self.length != Some(length) {
+            self.disable();
+        } else {
+            self.length = Some(length);
+            self.nb_probed += 1;
+        }
+    }
+
+    /// Stop detection, length will be None
+    pub fn disable(&mut self) {
+        self.length = None;
+        self.nb_probed = UNIFORM_PROBE;
+    }
+}
+
+/// Line ending of a file, see [line_ending](ProducerBuilder::line_ending)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Lines end by `\n`, a `\r` before it is keep in records
+    Lf,
+    /// Lines end by `\r\n`, `\r` is remove from records
+    Crlf,
+    /// Line ending is detect on first line of file
+    Auto,
+}
+
+/// Options of a producer, producer is create by `build` method implemented by each producer
+///
+/// Get a builder with `Producer::builder()`, options not set keep value use by `Producer::new`.
+pub struct ProducerBuilder<P> {
+    pub(crate) blocksize: Option<u64>,
+    #[cfg(unix)]
+    pub(crate) advice: Option<memmap2::Advice>,
+    pub(crate) populate: bool,
+    pub(crate) lenient: bool,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) capture_context: bool,
+    pub(crate) evict_consumed: bool,
+    pub(crate) progress: Option<Box<dyn FnMut(u64, u64) + Send>>,
+    producer: std::marker::PhantomData<P>,
+}
+
+impl<P> ProducerBuilder<P> {
+    pub fn new() -> Self {
+        Self {
+            blocksize: None,
+            #[cfg(unix)]
+            advice: None,
+            populate: false,
+            lenient: false,
+            line_ending: LineEnding::Lf,
+            capture_context: false,
+            evict_consumed: false,
+            progress: None,
+            producer: std::marker::PhantomData,
+        }
+    }
+
+    /// Set blocksize, by default blocksize is choose by [default_blocksize](crate::default_blocksize)
+    pub fn blocksize(mut self, blocksize: u64) -> Self {
+        self.blocksize = Some(blocksize);
+        self
+    }
+
+    /// Give an advice to kernel about how memory mapping of file will be use, default is sequential
+    ///
+    /// Only available on unix, some advices are Linux only, an advice refused by kernel is ignored with a warning.
+    #[cfg(unix)]
+    pub fn advice(mut self, advice: memmap2::Advice) -> Self {
+        self.advice = Some(advice);
+        self
+    }
+
+    /// If true page tables of mapping are prefault when file is map, reduce latency of first read of each page but map take longer
+    ///
+    /// It's MAP_POPULATE flag, only apply on Linux, it's ignored on other OS or if file isn't map.
+    pub fn populate(mut self, populate: bool) -> Self {
+        self.populate = populate;
+        self
+    }
+
+    /// If true an incomplete record at end of file isn't reported as a truncated file, reader will report it
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Set line ending of file, default is [Lf](LineEnding::Lf)
+    ///
+    /// With [Crlf](LineEnding::Crlf) blocks are mark by [set_crlf](Block::set_crlf) so readers remove `\r` at end of record lines.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// If true errors keep bytes around their offset, see [Error::with_context](crate::error::Error::with_context)
+    pub fn capture_context(mut self, capture_context: bool) -> Self {
+        self.capture_context = capture_context;
+        self
+    }
+
+    /// If true pages of a block are drop from memory when block is drop, see [Block::set_evict_on_drop]
+    pub fn evict_consumed(mut self, evict_consumed: bool) -> Self {
+        self.evict_consumed = evict_consumed;
+        self
+    }
+
+    /// Register a callback call with bytes consumed and total number of bytes after each block
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(u64, u64) + Send + 'static,
+    {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+}
+
+impl<P> Default for ProducerBuilder<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Future of next item of a stream, used by next_block of async producers
+#[cfg(feature = "async-tokio")]
+pub struct NextBlock<'a, S> {
+    stream: &'a mut S,
+}
+
+#[cfg(feature = "async-tokio")]
+impl<'a, S> NextBlock<'a, S> {
+    pub fn new(stream: &'a mut S) -> Self {
+        Self { stream }
+    }
+}
+
+#[cfg(feature = "async-tokio")]
+impl<S> std::future::Future for NextBlock<'_, S>
+where
+    S: futures_core::Stream + Unpin,
+{
+    type Output = Option<S::Item>;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        futures_core::Stream::poll_next(std::pin::Pin::new(&mut *self.stream), cx)
+    }
+}
+
+/// Follow mode of a producer, producer wait for new data at end of a growing file until end is signaled
+///
+/// Enable it with `follow` method of producer.
+#[derive(Debug, Clone)]
+pub struct Follow {
+    finished: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    interval: std::time::Duration,
+}
+
+impl Follow {
+    /// File is complete when finished is set to true, by default file length is check every 100 milliseconds
+    pub fn new(finished: std::sync::Arc<std::sync::atomic::AtomicBool>) -> Self {
+        Self {
+            finished,
+            interval: std::time::Duration::from_millis(100),
+        }
+    }
+
+    /// Set time between two check of file length
+    pub fn interval(mut self, interval: std::time::Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// True if end of file is signaled
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    /// Wait before next check of file length
+    pub fn wait(&self) {
+        std::thread::sleep(self.interval)
+    }
+}
+
 #[macro_export(local_inner_macros)]
 macro_rules! impl_producer {
-    ($name:ident, $correct_block_size:expr) => {
+    ($name:ident, $correct_block_size:expr, $find_record_start:expr, $truncated_record:expr) => {
         pub struct $name {
             offset: u64,
             blocksize: u64,
-            file: std::fs::File,
+            file: Option<std::fs::File>,
             file_length: u64,
+            mem: Option<std::sync::Arc<block::Buffer>>,
+            progress: Option<Box<dyn FnMut(u64, u64) + Send>>,
+            lenient: bool,
+            crlf: bool,
+            capture_context: bool,
+            evict_consumed: bool,
+            populate: bool,
+            follow: Option<block::Follow>,
+        }
+
+        impl block::ProducerBuilder<$name> {
+            /// Build producer of file with options of builder
+            pub fn build<P>(self, path: P) -> error::Result<$name>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                let file = std::fs::File::open(path)
+                    .map_err(|source| error::Error::OpenFile { source })?;
+
+                let mut producer = $name::from_file_with(
+                    self.blocksize.unwrap_or(u64::MAX),
+                    file,
+                    self.populate,
+                )?;
+                if self.blocksize.is_none() {
+                    producer.blocksize = $crate::default_blocksize(producer.file_length)
+                        .min(producer.file_length);
+                }
+
+                producer.lenient = self.lenient;
+                producer.crlf = match self.line_ending {
+                    block::LineEnding::Lf => false,
+                    block::LineEnding::Crlf => true,
+                    block::LineEnding::Auto => producer.first_line_crlf()?,
+                };
+                producer.capture_context = self.capture_context;
+                producer.evict_consumed = self.evict_consumed;
+                producer.progress = self.progress;
+
+                #[cfg(unix)]
+                if let Some(advice) = self.advice {
+                    if let Err(e) = producer.advise(advice) {
+                        log::warn!("in_place_fastx can't give advice {:?} to kernel {}, it's ignored", advice, e);
+                    }
+                }
+
+                Ok(producer)
+            }
         }
 
         impl $name {
-            /// Create a new Block producer
+            /// Get a builder to set options of producer
+            pub fn builder() -> block::ProducerBuilder<Self> {
+                block::ProducerBuilder::new()
+            }
+
+            /// Create a new Block producer, blocksize is choose by [default_blocksize]($crate::default_blocksize)
             #[inline(always)]
             pub fn new<P>(path: P) -> error::Result<Self>
             where
                 P: AsRef<std::path::Path>,
                 Self: Sized,
             {
-                Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, path)
+                let mut producer = Self::with_blocksize(u64::MAX, path)?;
+                producer.blocksize = $crate::default_blocksize(producer.file_length)
+                    .min(producer.file_length);
+
+                Ok(producer)
             }
 
             pub fn with_blocksize<P>(blocksize: u64, path: P) -> error::Result<Self>
             where
                 P: AsRef<std::path::Path>,
             {
-                Ok(Self {
+                let file = std::fs::File::open(path)
+                    .map_err(|source| error::Error::OpenFile { source })?;
+
+                Self::from_file(blocksize, file)
+            }
+
+            /// Create a Block producer on an already open file, file length is read from file metadata
+            ///
+            /// A [UTF-8 byte order mark](block::UTF8_BOM) at begin of file is skipped.
+            pub fn from_file(blocksize: u64, file: std::fs::File) -> error::Result<Self> {
+                Self::from_file_with(blocksize, file, false)
+            }
+
+            /// Same as from_file, if populate is true page tables of mapping are prefault, see [populate](block::ProducerBuilder::populate)
+            fn from_file_with(
+                blocksize: u64,
+                file: std::fs::File,
+                populate: bool,
+            ) -> error::Result<Self> {
+                let file_length = file
+                    .metadata()
+                    .map_err(|source| error::Error::MetaDataFile { source })?
+                    .len();
+
+                let mem = Self::map(&file, file_length, populate);
+
+                let mut producer = Self {
                     offset: 0,
-                    blocksize: Self::fix_blocksize::<P>(&path, blocksize)?,
-                    file_length: Self::filesize::<P>(&path)?,
-                    file: std::fs::File::open(path)
-                        .map_err(|source| error::Error::OpenFile { source })?,
-                })
+                    blocksize: file_length.min(blocksize),
+                    file_length,
+                    file: Some(file),
+                    mem,
+                    progress: None,
+                    lenient: false,
+                    crlf: false,
+                    capture_context: false,
+                    evict_consumed: false,
+                    populate,
+                    follow: None,
+                };
+
+                if file_length >= block::UTF8_BOM.len() as u64
+                    && producer.block_range(0, block::UTF8_BOM.len() as u64)?.data() == block::UTF8_BOM
+                {
+                    producer.offset = block::UTF8_BOM.len() as u64;
+                }
+
+                // file is read linearly, kernel readahead could be more aggressive, it's only a hint so error is ignored
+                #[cfg(unix)]
+                let _ = producer.advise(memmap2::Advice::Sequential);
+
+                Ok(producer)
+            }
+
+            /// Whole file is map once, each block is a view on this mapping, if mapping isn't possible block are read from file
+            ///
+            /// An empty file is never map, mapping zero bytes fail on some platforms.
+            fn map(
+                file: &std::fs::File,
+                file_length: u64,
+                populate: bool,
+            ) -> Option<std::sync::Arc<block::Buffer>> {
+                if file_length == 0 || !$crate::block::USE_MMAP {
+                    return None;
+                }
+
+                let mut options = memmap2::MmapOptions::new();
+                options.len(file_length as usize);
+                if populate {
+                    options.populate();
+                }
+
+                match unsafe { options.map(file) } {
+                    Ok(mem) => Some(std::sync::Arc::new(block::Buffer::Mmap(mem))),
+                    Err(e) => {
+                        log::warn!("in_place_fastx can't map file on memory {}, fallback on read", e);
+                        None
+                    }
+                }
+            }
+
+            /// Create a Block producer on an open file descriptor, producer take ownership of file descriptor
+            ///
+            /// # Safety
+            ///
+            /// fd must be an open file descriptor not own by anything else, see [FromRawFd](std::os::unix::io::FromRawFd)
+            #[cfg(unix)]
+            pub unsafe fn from_raw_fd(
+                blocksize: u64,
+                fd: std::os::unix::io::RawFd,
+            ) -> error::Result<Self> {
+                Self::from_file(
+                    blocksize,
+                    <std::fs::File as std::os::unix::io::FromRawFd>::from_raw_fd(fd),
+                )
+            }
+
+            /// Create a Block producer on data store in memory
+            pub fn from_vec(blocksize: u64, data: Vec<u8>) -> Self {
+                let file_length = data.len() as u64;
+
+                Self {
+                    offset: if data.starts_with(&block::UTF8_BOM) {
+                        block::UTF8_BOM.len() as u64
+                    } else {
+                        0
+                    },
+                    blocksize: file_length.min(blocksize),
+                    file_length,
+                    file: None,
+                    mem: Some(std::sync::Arc::new(block::Buffer::Vec(data))),
+                    progress: None,
+                    lenient: false,
+                    crlf: false,
+                    capture_context: false,
+                    evict_consumed: false,
+                    populate: false,
+                    follow: None,
+                }
+            }
+
+            /// Register a callback call with bytes consumed and total number of bytes after each block
+            ///
+            /// Callback is call by thread that request next block, it must be fast to not delay other workers
+            pub fn on_progress<F>(mut self, callback: F) -> Self
+            where
+                F: FnMut(u64, u64) + Send + 'static,
+            {
+                self.progress = Some(Box::new(callback));
+                self
+            }
+
+            /// Give an advice to kernel about how memory mapping of file will be use, only available on unix
+            #[cfg(unix)]
+            pub fn advise(&self, advice: memmap2::Advice) -> error::Result<()> {
+                match self.mem.as_deref() {
+                    Some(block::Buffer::Mmap(mem)) => mem
+                        .advise(advice)
+                        .map_err(|source| error::Error::MapFile { source }),
+                    _ => Ok(()),
+                }
+            }
+
+            /// Ask kernel to load whole file in page cache, return once readahead is issued without waiting for load, only available on unix
+            ///
+            /// Call before a timed parsing to avoid cold cache misses, it's a no-op if file isn't map.
+            #[cfg(unix)]
+            pub fn prefetch(&self) -> error::Result<()> {
+                self.advise(memmap2::Advice::WillNeed)
+            }
+
+            /// Ask kernel to drop pages of file mapping, next access read them again, only available on unix
+            ///
+            /// Pages could stay in page cache, it's a no-op if file isn't map.
+            #[cfg(unix)]
+            pub fn evict(&self) -> error::Result<()> {
+                self.advise(memmap2::Advice::DontNeed)
+            }
+
+            /// If true errors keep bytes around their offset, see [Error::with_context](error::Error::with_context)
+            pub fn set_capture_context(&mut self, value: bool) {
+                self.capture_context = value;
+            }
+
+            /// If true blocks produced ask kernel to drop their pages when they are drop, see [Block::set_evict_on_drop](block::Block::set_evict_on_drop)
+            ///
+            /// Keep resident memory small when a large file is read once.
+            pub fn set_evict_consumed(&mut self, value: bool) {
+                self.evict_consumed = value;
+            }
+
+            fn context(&self, error: error::Error, block: &block::Block) -> error::Error {
+                if self.capture_context {
+                    error.with_context(block.data(), block.file_offset())
+                } else {
+                    error
+                }
+            }
+
+            /// Enable follow mode, at end of file producer wait until file grow or end of file is signaled
+            ///
+            /// An incomplete record at end of file is never send before rest of record is write or end is signaled. Blocksize replace blocksize of producer, it isn't bounded by current file length.
+            pub fn follow(mut self, blocksize: u64, follow: block::Follow) -> Self {
+                self.blocksize = blocksize;
+                self.follow = Some(follow);
+                self
+            }
+
+            /// Read file length again, if file grew it's map again, return true if file grew
+            ///
+            /// Blocks already produced keep the previous mapping.
+            pub fn refresh(&mut self) -> error::Result<bool> {
+                let file = match self.file.as_ref() {
+                    Some(file) => file,
+                    None => return Ok(false),
+                };
+
+                let file_length = file
+                    .metadata()
+                    .map_err(|source| error::Error::MetaDataFile { source })?
+                    .len();
+
+                if file_length <= self.file_length {
+                    return Ok(false);
+                }
+
+                self.mem = Self::map(file, file_length, self.populate);
+                self.file_length = file_length;
+
+                Ok(true)
             }
 
             pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
-                if self.offset() == self.file_length() {
-                    Ok(None)
-                } else if self.offset() + self.blocksize() >= self.file_length() {
-                    let block = unsafe {
-                        memmap2::MmapOptions::new()
-                            .offset(self.offset())
-                            .len((self.file_length() - self.offset()) as usize)
-                            .map(self.file())
-                            .map_err(|source| error::Error::MapFile { source })?
+                let mut block = if self.follow.is_some() {
+                    self.next_block_follow()?
+                } else {
+                    self.next_block_fixed()?
+                };
+
+                if let Some(block) = block.as_mut() {
+                    block.set_evict_on_drop(self.evict_consumed);
+                    block.set_crlf(self.crlf);
+                }
+
+                Ok(block)
+            }
+
+            fn next_block_follow(&mut self) -> error::Result<Option<block::Block>> {
+                loop {
+                    // end is read before length, after that file length is final
+                    let finished = self.follow.as_ref().map_or(true, |follow| follow.is_finished());
+                    self.refresh()?;
+
+                    if finished {
+                        return self.next_block_fixed();
+                    }
+
+                    let begin = self.offset();
+                    if begin < self.file_length() {
+                        let end = (begin + self.blocksize()).min(self.file_length());
+                        let mut block = self.block_range(begin, end)?;
+
+                        // last line of file could be incomplete, it's never send
+                        let complete = if end == self.file_length() {
+                            memchr::memrchr(b'\n', block.data()).map_or(0, |pos| pos + 1)
+                        } else {
+                            block.len()
+                        };
+
+                        match Self::correct_block_size(&block.data()[..complete]) {
+                            Ok(0) | Err(error::Error::NoNewLineInBlock { .. }) if end == self.file_length() => (),
+                            Ok(0) | Err(error::Error::NoNewLineInBlock { .. }) => {
+                                return Err(self.blocksize_too_small(begin))
+                            }
+                            Ok(blocksize) => {
+                                block.truncate(blocksize as usize);
+                                self.set_offset(begin + blocksize);
+
+                                if let Some(progress) = self.progress.as_mut() {
+                                    progress(self.offset, self.file_length);
+                                }
+
+                                return Ok(Some(block));
+                            }
+                            Err(e) => return Err(self.context(e.add_offset(begin), &block)),
+                        }
+                    }
+
+                    if let Some(follow) = self.follow.as_ref() {
+                        follow.wait();
+                    }
+                }
+            }
+
+            fn next_block_fixed(&mut self) -> error::Result<Option<block::Block>> {
+                // empty file or end of file, blocksize could be 0 here
+                if self.file_length() == 0 || self.offset() >= self.file_length() {
+                    return Ok(None);
+                }
+
+                let begin = self.offset();
+                let mut end = (begin + self.blocksize()).min(self.file_length());
+
+                let mut block = self.block_range(begin, end)?;
+
+                if end != self.file_length() {
+                    let blocksize = match Self::correct_block_size(block.data()) {
+                        Ok(0) | Err(error::Error::NoNewLineInBlock { .. }) => {
+                            return Err(self.blocksize_too_small(begin))
+                        }
+                        Ok(blocksize) => blocksize,
+                        Err(e) => return Err(self.context(e.add_offset(begin), &block)),
                     };
+                    block.truncate(blocksize as usize);
+                    end = begin + blocksize;
+                } else if block.data().iter().all(|c| *c == b'\n' || *c == b'\r') {
+                    // end of file contains only empty lines, there is no more record
+                    self.set_offset(end);
+                    return Ok(None);
+                } else if let Some(pos) = Self::truncated_record(block.data()).filter(|_| !self.lenient) {
+                    let error = error::Error::TruncatedFile {
+                        offset: begin + pos as u64,
+                    };
+                    return Err(self.context(error, &block));
+                }
 
-                    self.set_offset(self.file_length());
+                self.set_offset(end);
 
-                    Ok(Some(block::Block::new(block.len(), block)))
-                } else {
-                    let block = unsafe {
-                        memmap2::MmapOptions::new()
-                            .offset(self.offset())
-                            .len(self.blocksize() as usize)
-                            .map(self.file())
-                            .map_err(|source| error::Error::MapFile { source })?
+                if let Some(progress) = self.progress.as_mut() {
+                    progress(self.offset, self.file_length);
+                }
+
+                Ok(Some(block))
+            }
+
+            /// Build error for a block that start at begin and didn't contain a complete record
+            ///
+            /// Window is double until a record boundary is found, to report a blocksize large enough.
+            fn blocksize_too_small(&self, begin: u64) -> error::Error {
+                let mut length = self.blocksize().max(1);
+
+                loop {
+                    length = length.saturating_mul(2);
+                    let end = begin.saturating_add(length).min(self.file_length());
+
+                    if end == self.file_length() {
+                        return error::Error::BlocksizeTooSmall {
+                            blocksize: self.blocksize(),
+                            min_needed: end - begin,
+                        };
+                    }
+
+                    let block = match self.block_range(begin, end) {
+                        Ok(block) => block,
+                        Err(e) => return e,
                     };
 
-                    let blocksize = Self::correct_block_size(&block)?;
-                    self.set_offset(self.offset() + blocksize);
-                    Ok(Some(block::Block::new(blocksize as usize, block)))
+                    if let Ok(blocksize) = Self::correct_block_size(block.data()) {
+                        if blocksize != 0 {
+                            return error::Error::BlocksizeTooSmall {
+                                blocksize: self.blocksize(),
+                                min_needed: length,
+                            };
+                        }
+                    }
+                }
+            }
+
+            /// Get a Block on section begin..end of file, boundary of section isn't check
+            pub fn block_range(&self, begin: u64, end: u64) -> error::Result<block::Block> {
+                match (&self.mem, &self.file) {
+                    (Some(mem), _) => Ok(block::Block::from_shared(
+                        mem.clone(),
+                        begin as usize,
+                        end as usize,
+                    )),
+                    (None, Some(file)) => {
+                        Ok(block::Block::from_vec(begin, Self::read(file, begin, end)?))
+                    }
+                    (None, None) => Ok(block::Block::from_vec(begin, std::vec::Vec::new())),
+                }
+            }
+
+            fn first_line_crlf(&self) -> error::Result<bool> {
+                let end = self.file_length.min(self.offset.saturating_add(self.blocksize));
+                let block = self.block_range(self.offset, end)?;
+
+                Ok(match memchr::memchr(b'\n', block.data()) {
+                    Some(pos) => pos > 0 && block.data()[pos - 1] == b'\r',
+                    None => false,
+                })
+            }
+
+            /// Compute end of nb_chunk section of file with similar size, each end is a record boundary
+            ///
+            /// Boundary is search in the last blocksize bytes before the ideal end of chunk.
+            pub fn chunk_ends(&self, nb_chunk: u64) -> error::Result<Vec<u64>> {
+                let nb_chunk = nb_chunk.max(1);
+                let mut ends = std::vec::Vec::with_capacity(nb_chunk as usize);
+
+                let mut previous = self.offset();
+                for i in 1..nb_chunk {
+                    let target = self.file_length() * i / nb_chunk;
+
+                    if target > previous {
+                        let begin = previous.max(target.saturating_sub(self.blocksize()));
+                        let block = self.block_range(begin, target)?;
+                        previous = begin
+                            + Self::correct_block_size(block.data())
+                                .map_err(|e| e.add_offset(begin))?;
+                    }
+
+                    ends.push(previous);
+                }
+                ends.push(self.file_length());
+
+                Ok(ends)
+            }
+
+            /// Read begin..end of file at its offset, file position isn't use so file could be share between threads
+            fn read(file: &std::fs::File, begin: u64, end: u64) -> error::Result<Vec<u8>> {
+                let mut data = std::vec![0; (end - begin) as usize];
+
+                Self::read_at(file, &mut data, begin)
+                    .map_err(|source| error::Error::ReadFile { source })?;
+
+                Ok(data)
+            }
+
+            #[cfg(unix)]
+            fn read_at(file: &std::fs::File, data: &mut [u8], offset: u64) -> std::io::Result<()> {
+                std::os::unix::fs::FileExt::read_exact_at(file, data, offset)
+            }
+
+            #[cfg(windows)]
+            fn read_at(file: &std::fs::File, mut data: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+                while !data.is_empty() {
+                    match std::os::windows::fs::FileExt::seek_read(file, data, offset) {
+                        Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                        Ok(n) => {
+                            data = &mut data[n..];
+                            offset += n as u64;
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+
+                Ok(())
+            }
+
+            #[cfg(not(any(unix, windows)))]
+            fn read_at(mut file: &std::fs::File, data: &mut [u8], offset: u64) -> std::io::Result<()> {
+                use std::io::Read;
+                use std::io::Seek;
+
+                file.seek(std::io::SeekFrom::Start(offset))?;
+                file.read_exact(data)
+            }
+
+            /// Get file size
+            pub fn filesize<P>(path: &P) -> error::Result<u64>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                Ok(path
+                    .as_ref()
+                    .metadata()
+                    .map_err(|source| error::Error::MetaDataFile { source })?
+                    .len())
+            }
+
+            /// Fix blocksize
+            pub fn fix_blocksize<P>(path: &P, blocksize: u64) -> error::Result<u64>
+            where
+                P: AsRef<std::path::Path>,
+                Self: Sized,
+            {
+                Ok(Self::filesize::<P>(path)?.min(blocksize))
+            }
+
+            /// Search the begin of the partial record at the end of [Block](Block)
+            #[inline(always)]
+            pub fn correct_block_size(block: &[u8]) -> error::Result<u64> {
+                $correct_block_size(block)
+            }
+
+            /// Search the begin of the first record that follow a new line in data
+            #[inline(always)]
+            pub fn find_record_start(data: &[u8]) -> Option<usize> {
+                $find_record_start(data)
+            }
+
+            /// Search the begin of an incomplete record at the end of file data, None if last record is complete
+            ///
+            /// Trailing new lines are ignored, a last record without new line is complete.
+            #[inline(always)]
+            pub fn truncated_record(data: &[u8]) -> Option<usize> {
+                $truncated_record(data)
+            }
+
+            /// Move producer to offset, if offset isn't a record boundary producer move to the next record
+            ///
+            /// Boundary is search in the next blocksize bytes after offset.
+            pub fn seek_to(&mut self, offset: u64) -> error::Result<()> {
+                if offset == 0 || offset >= self.file_length() {
+                    self.set_offset(offset.min(self.file_length()));
+                    return Ok(());
+                }
+
+                // keep previous byte to detect if offset is just after a new line
+                let begin = offset - 1;
+                let end = (begin + self.blocksize() + 1).min(self.file_length());
+                let block = self.block_range(begin, end)?;
+
+                match Self::find_record_start(block.data()) {
+                    Some(pos) => self.set_offset(begin + pos as u64),
+                    None if end == self.file_length() => self.set_offset(end),
+                    None => return Err(error::Error::NoNewLineInBlock { offset: begin }),
+                }
+
+                Ok(())
+            }
+
+            /// Get position of the next Block in file, can be use with seek_to to resume parsing
+            pub fn position(&self) -> u64 {
+                self.offset
+            }
+
+            /// Get current value of offset
+            pub fn offset(&self) -> u64 {
+                self.offset
+            }
+
+            /// Get file length
+            pub fn file_length(&self) -> u64 {
+                self.file_length
+            }
+
+            /// Get file, None if producer read data in memory
+            pub fn file(&self) -> Option<&std::fs::File> {
+                self.file.as_ref()
+            }
+
+            /// Get blocksize
+            pub fn blocksize(&self) -> u64 {
+                self.blocksize
+            }
+
+            /// Set value of offset
+            pub fn set_offset(&mut self, value: u64) {
+                self.offset = value;
+            }
+        }
+
+        impl Iterator for $name {
+            type Item = error::Result<block::Block>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                match self.next_block() {
+                    Ok(Some(block)) => Some(Ok(block)),
+                    Ok(None) => None,
+                    Err(e) => Some(Err(e)),
                 }
             }
+        }
+    };
+}
+
+#[macro_export(local_inner_macros)]
+macro_rules! impl_chunked_producer {
+    ($name:ident, $producer:ident) => {
+        /// Split file in a fixed number of Block, each block end on a record boundary
+        pub struct $name {
+            producer: $producer,
+            ends: std::vec::IntoIter<u64>,
+        }
+
+        impl $name {
+            /// Create a producer that split file in nb_threads blocks
+            pub fn with_threads<P>(nb_threads: u64, path: P) -> error::Result<Self>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                let producer = $producer::new(path)?;
+                let ends = producer.chunk_ends(nb_threads)?.into_iter();
+
+                Ok(Self { producer, ends })
+            }
+
+            pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+                match self.ends.next() {
+                    Some(end) => {
+                        let block = self.producer.block_range(self.producer.offset(), end)?;
+                        self.producer.set_offset(end);
+                        Ok(Some(block))
+                    }
+                    None => Ok(None),
+                }
+            }
+        }
+
+        impl Iterator for $name {
+            type Item = error::Result<block::Block>;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                match self.next_block() {
+                    Ok(Some(block)) => Some(Ok(block)),
+                    Ok(None) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "async-tokio")]
+#[macro_export(local_inner_macros)]
+macro_rules! impl_async_producer {
+    ($name:ident, $producer:ident) => {
+        /// Produce blocks in a tokio runtime, file mapping and block search run in [spawn_blocking](tokio::task::spawn_blocking)
+        ///
+        /// Blocks are get as a [Stream](futures_core::Stream) or with next_block, stream end after first error. Records of a block could be read by a Reader in spawn_blocking.
+        pub struct $name {
+            producer: Option<$producer>,
+            pending:
+                Option<tokio::task::JoinHandle<($producer, error::Result<Option<block::Block>>)>>,
+        }
 
-            /// Get file size
-            pub fn filesize<P>(path: &P) -> error::Result<u64>
+        impl $name {
+            /// Create a new async Block producer, blocksize is choose by [default_blocksize]($crate::default_blocksize)
+            pub async fn new<P>(path: P) -> error::Result<Self>
             where
                 P: AsRef<std::path::Path>,
             {
-                Ok(path
-                    .as_ref()
+                let file = tokio::fs::File::open(path)
+                    .await
+                    .map_err(|source| error::Error::OpenFile { source })?;
+                let file_length = file
                     .metadata()
+                    .await
                     .map_err(|source| error::Error::MetaDataFile { source })?
-                    .len())
+                    .len();
+
+                Self::from_file($crate::default_blocksize(file_length), file).await
             }
 
-            /// Fix blocksize
-            pub fn fix_blocksize<P>(path: &P, blocksize: u64) -> error::Result<u64>
+            pub async fn with_blocksize<P>(blocksize: u64, path: P) -> error::Result<Self>
             where
                 P: AsRef<std::path::Path>,
-                Self: Sized,
             {
-                Ok(Self::filesize::<P>(path)?.min(blocksize))
-            }
+                let file = tokio::fs::File::open(path)
+                    .await
+                    .map_err(|source| error::Error::OpenFile { source })?;
 
-            /// Search the begin of the partial record at the end of [Block](Block)
-            #[inline(always)]
-            pub fn correct_block_size(block: &[u8]) -> error::Result<u64> {
-                $correct_block_size(block)
+                Self::from_file(blocksize, file).await
             }
 
-            /// Get current value of offset
-            pub fn offset(&self) -> u64 {
-                self.offset
-            }
+            /// Create an async Block producer on an already open file
+            pub async fn from_file(blocksize: u64, file: tokio::fs::File) -> error::Result<Self> {
+                let file = file.into_std().await;
+                let producer =
+                    tokio::task::spawn_blocking(move || $producer::from_file(blocksize, file))
+                        .await
+                        .map_err(|source| error::Error::Worker {
+                            source: Box::new(source),
+                        })??;
 
-            /// Get file length
-            pub fn file_length(&self) -> u64 {
-                self.file_length
+                Ok(Self::from_producer(producer))
             }
 
-            /// Get file
-            pub fn file(&self) -> &std::fs::File {
-                &self.file
+            /// Produce blocks of producer in a tokio runtime
+            pub fn from_producer(producer: $producer) -> Self {
+                Self {
+                    producer: Some(producer),
+                    pending: None,
+                }
             }
 
-            /// Get blocksize
-            pub fn blocksize(&self) -> u64 {
-                self.blocksize
+            /// Get next block, None at end of file or after an error
+            pub async fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+                block::NextBlock::new(self).await.transpose()
             }
 
-            /// Set value of offset
-            pub fn set_offset(&mut self, value: u64) {
-                self.offset = value;
+            fn poll_block(
+                &mut self,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<Option<error::Result<block::Block>>> {
+                if self.pending.is_none() {
+                    let mut producer = match self.producer.take() {
+                        Some(producer) => producer,
+                        None => return std::task::Poll::Ready(None),
+                    };
+
+                    self.pending = Some(tokio::task::spawn_blocking(move || {
+                        let block = producer.next_block();
+                        (producer, block)
+                    }));
+                }
+
+                let pending = match self.pending.as_mut() {
+                    Some(pending) => pending,
+                    None => return std::task::Poll::Ready(None),
+                };
+
+                let result = match std::future::Future::poll(std::pin::Pin::new(pending), cx) {
+                    std::task::Poll::Ready(result) => result,
+                    std::task::Poll::Pending => return std::task::Poll::Pending,
+                };
+                self.pending = None;
+
+                // producer is only keep if there is more block
+                std::task::Poll::Ready(match result {
+                    Ok((producer, Ok(Some(block)))) => {
+                        self.producer = Some(producer);
+                        Some(Ok(block))
+                    }
+                    Ok((_, Ok(None))) => None,
+                    Ok((_, Err(e))) => Some(Err(e)),
+                    Err(source) => Some(Err(error::Error::Worker {
+                        source: Box::new(source),
+                    })),
+                })
             }
         }
 
-        impl Iterator for $name {
+        impl futures_core::Stream for $name {
             type Item = error::Result<block::Block>;
 
-            fn next(&mut self) -> Option<Self::Item> {
-                match self.next_block() {
-                    Ok(Some(block)) => Some(Ok(block)),
-                    Ok(None) => None,
-                    Err(e) => Some(Err(e)),
-                }
+            fn poll_next(
+                mut self: std::pin::Pin<&mut Self>,
+                cx: &mut std::task::Context<'_>,
+            ) -> std::task::Poll<Option<Self::Item>> {
+                self.poll_block(cx)
             }
         }
     };
@@ -173,28 +1583,293 @@ macro_rules! impl_producer {
 #[macro_export(local_inner_macros)]
 macro_rules! impl_reader {
     ($name:ident, $next_record:expr) => {
+        impl_reader!($name, $next_record, |_record: &block::Record,
+                                           _offset: u64|
+         -> error::Result<()> { Ok(()) });
+    };
+    ($name:ident, $next_record:expr, $check_record:expr) => {
+        impl_reader!(
+            @reader $name,
+            $next_record,
+            $check_record,
+            |_block: &'a block::Block, _offset: &mut usize, _length: usize| None,
+            false
+        );
+    };
+    ($name:ident, $next_record:expr, $check_record:expr, $next_uniform_record:expr) => {
+        impl_reader!(
+            @reader $name,
+            $next_record,
+            $check_record,
+            $next_uniform_record,
+            true
+        );
+    };
+    (@reader $name:ident, $next_record:expr, $check_record:expr, $next_uniform_record:expr, $detect_uniform:expr) => {
         pub struct $name {
             offset: usize,
             block: block::Block,
+            validate: bool,
+            skip_blank_lines: bool,
+            skip_comment_lines: bool,
+            alphabet: Option<Vec<u8>>,
+            capture_context: bool,
+            uniform: block::UniformLength,
         }
 
         impl $name {
             pub fn new(block: block::Block) -> Self {
-                Reader { offset: 0, block }
+                $name {
+                    offset: 0,
+                    block,
+                    validate: false,
+                    skip_blank_lines: false,
+                    skip_comment_lines: false,
+                    alphabet: None,
+                    capture_context: false,
+                    uniform: block::UniformLength::new($detect_uniform),
+                }
+            }
+
+            /// Create a Reader that check each record, this check have a cost
+            pub fn new_validated(block: block::Block) -> Self {
+                $name {
+                    offset: 0,
+                    block,
+                    validate: true,
+                    skip_blank_lines: false,
+                    skip_comment_lines: false,
+                    alphabet: None,
+                    capture_context: false,
+                    uniform: block::UniformLength::new(false),
+                }
+            }
+
+            /// If true empty lines between records are ignored, empty lines inside a record are still an error
+            pub fn set_skip_blank_lines(&mut self, value: bool) {
+                self.skip_blank_lines = value;
+            }
+
+            /// If true lines that start by `;` between records are ignored, old fasta files use them as comment
+            pub fn set_skip_comment_lines(&mut self, value: bool) {
+                self.skip_comment_lines = value;
+            }
+
+            /// If set, a sequence with a byte not in alphabet produce an [InvalidBase](error::Error::InvalidBase) error
+            pub fn set_alphabet(&mut self, alphabet: Option<&[u8]>) {
+                self.alphabet = alphabet.map(|allowed| allowed.to_vec());
+            }
+
+            /// If true errors keep bytes around their offset, see [Error::with_context](error::Error::with_context)
+            pub fn set_capture_context(&mut self, value: bool) {
+                self.capture_context = value;
+            }
+
+            /// Get length of all sequences if first [UNIFORM_PROBE](block::UNIFORM_PROBE) records have the same length
+            ///
+            /// When length is known, reader jump over sequence and quality lines instead of search their end. Validated reader never detect it.
+            pub fn uniform_length(&self) -> Option<usize> {
+                self.uniform.length()
+            }
+
+            /// Get offset in block of next record
+            pub fn offset(&self) -> usize {
+                self.offset
+            }
+
+            /// Get offset in file of next record
+            pub fn file_offset(&self) -> u64 {
+                self.block.file_offset() + self.offset as u64
+            }
+
+            /// Get block read by this reader
+            pub fn block(&self) -> &block::Block {
+                &self.block
             }
 
             #[inline(always)]
             pub fn next_record<'a>(&'a mut self) -> error::Result<Option<block::Record<'a>>> {
-                $next_record(&mut self.block, &mut self.offset)
+                let result = Self::read_checked_record(
+                    &self.block,
+                    &mut self.offset,
+                    self.validate,
+                    self.skip_blank_lines,
+                    self.skip_comment_lines,
+                    self.alphabet.as_deref(),
+                    &mut self.uniform,
+                );
+
+                if self.capture_context {
+                    let block = &self.block;
+                    return result.map_err(|e| e.with_context(block.data(), block.file_offset()));
+                }
+
+                result
+            }
+
+            /// Iterate over remaining records, iteration stop after first error
+            pub fn records(
+                &mut self,
+            ) -> impl Iterator<Item = error::Result<block::Record<'_>>> + '_ {
+                let block = &self.block;
+                let offset = &mut self.offset;
+                let validate = self.validate;
+                let skip_blank_lines = self.skip_blank_lines;
+                let skip_comment_lines = self.skip_comment_lines;
+                let alphabet = self.alphabet.as_deref();
+                let capture_context = self.capture_context;
+                let uniform = &mut self.uniform;
+                let mut failed = false;
+
+                std::iter::from_fn(move || {
+                    if failed {
+                        return None;
+                    }
+
+                    match Self::read_checked_record(
+                        block,
+                        offset,
+                        validate,
+                        skip_blank_lines,
+                        skip_comment_lines,
+                        alphabet,
+                        uniform,
+                    ) {
+                        Ok(record) => record.map(Ok),
+                        Err(e) if capture_context => {
+                            failed = true;
+                            Some(Err(e.with_context(block.data(), block.file_offset())))
+                        }
+                        Err(e) => {
+                            failed = true;
+                            Some(Err(e))
+                        }
+                    }
+                })
+            }
+
+            /// Call f on each remaining records, stop at first error
+            pub fn for_each<F>(&mut self, mut f: F) -> error::Result<()>
+            where
+                F: FnMut(block::Record),
+            {
+                while let Some(record) = self.next_record()? {
+                    f(record);
+                }
+
+                Ok(())
+            }
+
+            #[inline(always)]
+            fn read_checked_record<'a>(
+                block: &'a block::Block,
+                offset: &mut usize,
+                validate: bool,
+                skip_blank_lines: bool,
+                skip_comment_lines: bool,
+                alphabet: Option<&[u8]>,
+                uniform: &mut block::UniformLength,
+            ) -> error::Result<Option<block::Record<'a>>> {
+                if skip_blank_lines || skip_comment_lines {
+                    loop {
+                        match block.data().get(*offset) {
+                            Some(b'\n') | Some(b'\r') if skip_blank_lines => *offset += 1,
+                            Some(b';') if skip_comment_lines => {
+                                *offset = match memchr::memchr(b'\n', &block.data()[*offset..]) {
+                                    Some(end) => *offset + end + 1,
+                                    None => block.len(),
+                                }
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+
+                let start = block.file_offset() + *offset as u64;
+                let record = match Self::read_uniform_record(block, offset, uniform) {
+                    Some(record) => Some(record),
+                    None => Self::read_record(block, offset)?,
+                };
+
+                if let Some(record) = &record {
+                    uniform.update(record.sequence.len());
+
+                    if validate {
+                        $check_record(record, start)?;
+                    }
+
+                    if let Some(allowed) = alphabet {
+                        // sequence is the line after comment
+                        record.validate_alphabet(allowed).map_err(|pos| {
+                            error::Error::InvalidBase {
+                                byte: record.sequence[pos],
+                                offset: start
+                                    + (record.comment.len() + 1 + block.is_crlf() as usize + pos)
+                                        as u64,
+                            }
+                        })?;
+                    }
+                }
+
+                Ok(record)
+            }
+
+            /// Read record of block that start at offset and move offset at end of record
+            #[inline(always)]
+            pub fn read_record<'a>(
+                block: &'a block::Block,
+                offset: &mut usize,
+            ) -> error::Result<Option<block::Record<'a>>> {
+                let record = $next_record(block, offset)?;
+
+                if block.is_crlf() {
+                    Ok(record.map(block::Record::trim_carriage_return))
+                } else {
+                    Ok(record)
+                }
+            }
+
+            /// Read record with the fixed length fast path if sequence length is uniform, on failure offset is restore and fast path is disable
+            #[inline(always)]
+            fn read_uniform_record<'a>(
+                block: &'a block::Block,
+                offset: &mut usize,
+                uniform: &mut block::UniformLength,
+            ) -> Option<block::Record<'a>> {
+                let length = uniform.length()?;
+                if *offset >= block.len() {
+                    return None;
+                }
+
+                let start = *offset;
+                let record = $next_uniform_record(block, offset, length + block.is_crlf() as usize);
+                if record.is_none() {
+                    *offset = start;
+                    uniform.disable();
+                }
+
+                if block.is_crlf() {
+                    record.map(block::Record::trim_carriage_return)
+                } else {
+                    record
+                }
             }
 
+            /// Get range of line that start at offset, last line of block could not end by a new line
             pub fn get_line(
                 block: &block::Block,
                 offset: &usize,
             ) -> error::Result<std::ops::Range<usize>> {
-                let next = memchr::memchr(b'\n', &block.data()[*offset..])
-                    .ok_or(error::Error::PartialRecord)?;
-                let range = *offset..*offset + next;
+                if *offset >= block.len() {
+                    return Err(error::Error::PartialRecord {
+                        offset: block.file_offset() + block.len() as u64,
+                    });
+                }
+
+                let range = match memchr::memchr(b'\n', &block.data()[*offset..]) {
+                    Some(next) => *offset..*offset + next,
+                    None => *offset..block.len(),
+                };
 
                 Ok(range)
             }
@@ -202,6 +1877,79 @@ macro_rules! impl_reader {
     };
 }
 
+#[macro_export(local_inner_macros)]
+macro_rules! impl_lenient_reader {
+    ($name:ident, $reader:ident, $find_record_start:expr) => {
+        /// Reader that skip malformed records, reading restart at next plausible record start
+        ///
+        /// Each record is validated, byte range of skipped data are keep.
+        pub struct $name {
+            reader: $reader,
+            skipped: Vec<std::ops::Range<u64>>,
+        }
+
+        impl $name {
+            pub fn new(block: block::Block) -> Self {
+                $name {
+                    reader: $reader::new_validated(block),
+                    skipped: std::vec::Vec::new(),
+                }
+            }
+
+            pub fn next_record(&mut self) -> Option<block::Record<'_>> {
+                let block = &self.reader.block;
+                let offset = &mut self.reader.offset;
+
+                loop {
+                    let start = *offset;
+
+                    match $reader::read_checked_record(
+                        block,
+                        offset,
+                        true,
+                        self.reader.skip_blank_lines,
+                        self.reader.skip_comment_lines,
+                        self.reader.alphabet.as_deref(),
+                        &mut self.reader.uniform,
+                    ) {
+                        Ok(record) => return record,
+                        Err(e) => {
+                            let data = &block.data()[start..];
+                            let end = match $find_record_start(data) {
+                                Some(pos) => start + pos,
+                                None => block.len(),
+                            };
+
+                            log::warn!("in_place_fastx skip malformed record: {}", e);
+
+                            self.skipped.push(
+                                block.file_offset() + start as u64
+                                    ..block.file_offset() + end as u64,
+                            );
+                            *offset = end;
+                        }
+                    }
+                }
+            }
+
+            /// Records with a base not in alphabet are skipped
+            pub fn set_alphabet(&mut self, alphabet: Option<&[u8]>) {
+                self.reader.set_alphabet(alphabet);
+            }
+
+            /// Get file byte range of each skipped data
+            pub fn skipped(&self) -> &[std::ops::Range<u64>] {
+                &self.skipped
+            }
+
+            /// Get number of malformed records skipped
+            pub fn nb_skipped(&self) -> usize {
+                self.skipped.len()
+            }
+        }
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,4 +1987,361 @@ mod tests {
         assert_eq!(block.len(), 200);
         assert!(!block.is_empty());
     }
+
+    #[test]
+    fn records() {
+        let block = Block::from_slice(b">1\nACGT\n>2\nTGCA\n");
+        assert_eq!(
+            block
+                .records_fasta()
+                .map(|record| record.unwrap().sequence.to_vec())
+                .collect::<Vec<Vec<u8>>>(),
+            vec![b"ACGT".to_vec(), b"TGCA".to_vec()]
+        );
+
+        let block = Block::from_slice(b"@1\nACGT\n+\n!!!!\n@2\nTGCA\n");
+        let mut records = block.records_fastq();
+        assert_eq!(records.next().unwrap().unwrap().comment, b"@1");
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+
+        assert_eq!(Block::from_slice(b"").records_fastq().count(), 0);
+    }
+
+    #[test]
+    fn as_str() {
+        let block = Block::from_slice(b"@1 \xc3\xa9\nACGT\n+\n!!!!\n");
+        assert_eq!(block.try_as_str(), Ok("@1 \u{e9}\nACGT\n+\n!!!!\n"));
+
+        let record = Record {
+            comment: b"@1",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"!\xff!!",
+        };
+        assert_eq!(record.comment_str(), Ok("@1"));
+        assert_eq!(record.sequence_str(), Ok("ACGT"));
+        assert_eq!(record.plus_str(), Ok("+"));
+        assert_eq!(record.quality_str().unwrap_err().valid_up_to(), 1);
+
+        let block = Block::from_slice(b"@\xff\n\xfe\n+\n!\n");
+        assert_eq!(block.try_as_str().unwrap_err().valid_up_to(), 1);
+        let record = block.records_fastq().next().unwrap().unwrap();
+        assert!(record.comment_str().is_err());
+        assert!(record.sequence_str().is_err());
+        assert_eq!(record.plus_str(), Ok("+"));
+        assert_eq!(record.quality_str(), Ok("!"));
+    }
+
+    #[test]
+    fn is_fasta() {
+        let fasta = crate::fasta::Reader::new(Block::from_slice(b">1\nACGT\n"))
+            .next_record()
+            .unwrap()
+            .map(|record| (record.is_fasta(), record.quality.is_empty()));
+        assert_eq!(fasta, Some((true, true)));
+
+        let fastq = crate::fastq::Reader::new(Block::from_slice(b"@1\nACGT\n+\n!!!!\n"))
+            .next_record()
+            .unwrap()
+            .map(|record| record.is_fasta());
+        assert_eq!(fastq, Some(false));
+    }
+
+    #[test]
+    fn record_id_description() {
+        let mut record = Record {
+            comment: b"@read_1 length=150 sample=A",
+            sequence: b"",
+            plus: b"",
+            quality: b"",
+        };
+
+        assert_eq!(record.name(), b"read_1 length=150 sample=A");
+        assert_eq!(record.id(), b"read_1");
+        assert_eq!(record.description(), b"length=150 sample=A");
+
+        record.comment = b">read_2\tdescription";
+        assert_eq!(record.name(), b"read_2\tdescription");
+        assert_eq!(record.id(), b"read_2");
+        assert_eq!(record.description(), b"description");
+
+        record.comment = b"@read_3";
+        assert_eq!(record.id(), b"read_3");
+        assert_eq!(record.description(), b"");
+
+        record.comment = b"@";
+        assert_eq!(record.id(), b"");
+        assert_eq!(record.description(), b"");
+
+        record.comment = b"";
+        assert_eq!(record.name(), b"");
+        assert_eq!(record.id(), b"");
+        assert_eq!(record.description(), b"");
+    }
+
+    #[test]
+    fn base_counts() {
+        let mut record = Record {
+            comment: b">1",
+            sequence: b"ACGTacgtNNRYGG",
+            plus: b"",
+            quality: b"",
+        };
+
+        assert_eq!(record.base_counts(), [2, 2, 4, 2, 4]);
+        assert!((record.gc_content() - 0.6).abs() < f64::EPSILON);
+
+        record.sequence = b"NNNN";
+        assert_eq!(record.base_counts(), [0, 0, 0, 0, 4]);
+        assert_eq!(record.gc_content(), 0.0);
+    }
+
+    #[test]
+    fn pack_2bit() {
+        let mut record = Record {
+            comment: b">1",
+            sequence: b"ACGTacgtA",
+            plus: b"",
+            quality: b"",
+        };
+
+        let (packed, length) = record.pack_2bit();
+        assert_eq!(packed, vec![0b00011110, 0b00011110, 0b00000000]);
+        assert_eq!(length, 9);
+        assert_eq!(
+            crate::seq::unpack_2bit(&packed, length),
+            b"ACGTACGTA".to_vec()
+        );
+        assert_eq!(record.non_acgt_mask(), vec![0, 0]);
+
+        record.sequence = b"ANGTNNNNNN";
+        assert_eq!(record.non_acgt_mask(), vec![0b11110010, 0b11]);
+
+        record.sequence = b"";
+        assert_eq!(record.pack_2bit(), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn composition() {
+        let record = Record {
+            comment: b">1",
+            sequence: b"ACGTacgtNNnRY-",
+            plus: b"",
+            quality: b"",
+        };
+
+        assert_eq!(
+            record.composition(),
+            Composition {
+                a: 2,
+                c: 2,
+                g: 2,
+                t: 2,
+                n: 3,
+                lower: 4,
+                other: 3,
+            }
+        );
+        assert_eq!(record.count_n(), 3);
+        assert_eq!(record.count_softmasked(), 4);
+    }
+
+    #[test]
+    fn masked_sequence() {
+        let mut record = Record {
+            comment: b"@1",
+            sequence: b"ACGTA",
+            plus: b"+",
+            quality: b"?!5+?",
+        };
+
+        assert_eq!(record.masked_sequence(20, 33, b'N'), b"ANGNA".to_vec());
+        assert_eq!(record.masked_sequence(0, 33, b'N'), b"ACGTA".to_vec());
+
+        record.quality = b"";
+        assert_eq!(record.masked_sequence(20, 33, b'N'), b"ACGTA".to_vec());
+    }
+
+    #[test]
+    fn validate_alphabet() {
+        let record = Record {
+            comment: b">1",
+            sequence: b"ACGTNacgt",
+            plus: b"",
+            quality: b"",
+        };
+
+        assert_eq!(record.validate_alphabet(b"ACGTN"), Err(5));
+        assert_eq!(record.validate_alphabet(b"ACGTNacgt"), Ok(()));
+        assert_eq!(record.validate_alphabet(b""), Err(0));
+    }
+
+    #[test]
+    #[cfg(feature = "cocktail")]
+    fn kmers() {
+        let mut record = Record {
+            comment: b">1",
+            sequence: b"ACGTAGC",
+            plus: b"",
+            quality: b"",
+        };
+
+        assert_eq!(
+            record.kmers(5).collect::<Vec<u64>>(),
+            cocktail::tokenizer::Tokenizer::new(b"ACGTAGC", 5).collect::<Vec<u64>>()
+        );
+        assert_eq!(
+            record.canonical_kmers(5).collect::<Vec<u64>>(),
+            cocktail::tokenizer::Canonical::new(b"ACGTAGC", 5).collect::<Vec<u64>>()
+        );
+        assert_eq!(record.kmers(7).count(), 1);
+
+        record.sequence = b"ACG";
+        assert_eq!(record.kmers(5).count(), 0);
+        assert_eq!(record.canonical_kmers(5).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "xxhash-rust")]
+    fn hash() {
+        let record = Record {
+            comment: b"@1",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"!!!!",
+        };
+        let same_sequence = Record {
+            comment: b"@2",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"####",
+        };
+
+        assert_eq!(record.seq_hash(), xxhash_rust::xxh3::xxh3_64(b"ACGT"));
+        assert_eq!(record.seq_hash(), same_sequence.seq_hash());
+        assert_ne!(record.record_hash(), same_sequence.record_hash());
+        assert_eq!(
+            record.record_hash(),
+            record.to_owned().as_record().record_hash()
+        );
+
+        let shifted = Record {
+            comment: b"@1A",
+            sequence: b"CGT",
+            plus: b"+",
+            quality: b"!!!!",
+        };
+        assert_ne!(record.record_hash(), shifted.record_hash());
+    }
+
+    #[test]
+    fn quality_scores() {
+        let record = Record {
+            comment: b"@1",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"!+5 ",
+        };
+
+        assert_eq!(
+            record.quality_scores(33).collect::<Vec<u8>>(),
+            vec![0, 10, 20, 0]
+        );
+
+        let scores = record
+            .checked_quality_scores(33)
+            .collect::<Vec<crate::error::Result<u8>>>();
+        assert_eq!(
+            scores[..3]
+                .iter()
+                .map(|q| *q.as_ref().unwrap())
+                .collect::<Vec<u8>>(),
+            vec![0, 10, 20]
+        );
+        assert!(matches!(
+            scores[3],
+            Err(crate::error::Error::QualityBelowOffset {
+                quality: b' ',
+                phred_offset: 33
+            })
+        ));
+
+        let probs = record.error_probabilities(33).collect::<Vec<f64>>();
+        assert!((probs[0] - 1.0).abs() < f64::EPSILON);
+        assert!((probs[1] - 0.1).abs() < f64::EPSILON);
+        assert!((probs[2] - 0.01).abs() < f64::EPSILON);
+
+        assert!((record.mean_quality(33) - 7.5).abs() < f64::EPSILON);
+        assert_eq!(
+            Record {
+                quality: b"",
+                ..record
+            }
+            .mean_quality(33),
+            0.0
+        );
+
+        assert_eq!(record.median_quality(33), 0);
+        assert_eq!(
+            Record {
+                quality: b"!+5",
+                ..record
+            }
+            .median_quality(33),
+            10
+        );
+        assert_eq!(
+            Record {
+                quality: b"~\xff~",
+                ..record
+            }
+            .median_quality(33),
+            MAX_PHRED
+        );
+        assert_eq!(
+            Record {
+                quality: b"",
+                ..record
+            }
+            .median_quality(33),
+            0
+        );
+    }
+
+    #[test]
+    fn owned_record() {
+        let file = crate::tests::generate_fastq(42, 10, 150);
+        let mut producer = crate::fastq::Producer::new(file.path()).unwrap();
+
+        let mut records: Vec<OwnedRecord> = Vec::new();
+        while let Some(block) = producer.next_block().unwrap() {
+            let mut reader = crate::fastq::Reader::new(block);
+            while let Some(record) = reader.next_record().unwrap() {
+                records.push(record.into());
+            }
+        }
+
+        assert_eq!(records.len(), 10);
+        assert_eq!(records[0].comment, b"@0".to_vec());
+        assert_eq!(records[9].plus, b"+9".to_vec());
+        assert_eq!(records[4].sequence.len(), 150);
+        assert_eq!(records[4].quality.len(), 150);
+
+        let record = Record {
+            comment: b"@1",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"!!!!",
+        };
+        assert_eq!(
+            record.to_owned(),
+            OwnedRecord {
+                comment: b"@1".to_vec(),
+                sequence: b"ACGT".to_vec(),
+                plus: b"+".to_vec(),
+                quality: b"!!!!".to_vec(),
+            }
+        );
+    }
 }
@@ -0,0 +1,195 @@
+//! Remove duplicate sequences during parsing, only available with feature `dedup`.
+//!
+//! Parsers send first record of each distinct sequence to a callback. With a sequential parser callback get records in file order, with a shared state parser which record of a group of duplicates is keep isn't determinist.
+
+/* std use */
+use std::sync::atomic;
+
+/* crate use */
+use rayon::iter::ParallelBridge;
+use rayon::iter::ParallelIterator;
+
+/* project use */
+use crate::block;
+
+/// How sequences already seen are store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Store only [seq_hash](block::Record::seq_hash) of sequences, memory efficient but a hash collision drop a unique sequence
+    Hash,
+    /// Store a copy of sequences, exact but memory usage grow with total length of unique sequences
+    Exact,
+}
+
+/// Set of sequences already seen, shared between threads, each unique record is send to callback
+pub struct Dedup {
+    mode: Mode,
+    hashes: dashmap::DashSet<u64>,
+    sequences: dashmap::DashSet<Vec<u8>>,
+    nb_duplicate: atomic::AtomicU64,
+    callback: Box<dyn Fn(block::Record) + Send + Sync>,
+}
+
+impl Dedup {
+    pub fn new<F>(mode: Mode, callback: F) -> Self
+    where
+        F: Fn(block::Record) + Send + Sync + 'static,
+    {
+        Self {
+            mode,
+            hashes: dashmap::DashSet::new(),
+            sequences: dashmap::DashSet::new(),
+            nb_duplicate: atomic::AtomicU64::new(0),
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Get mode
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Register sequence of record, return true if it wasn't seen before
+    pub fn insert(&self, record: &block::Record) -> bool {
+        let unique = match self.mode {
+            Mode::Hash => self.hashes.insert(record.seq_hash()),
+            Mode::Exact => self.sequences.insert(record.sequence.to_vec()),
+        };
+
+        if !unique {
+            self.nb_duplicate.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+
+        unique
+    }
+
+    /// Send record to callback if its sequence wasn't seen before
+    pub fn forward(&self, record: block::Record) {
+        if self.insert(&record) {
+            (self.callback)(record)
+        }
+    }
+
+    /// Get number of distinct sequences seen
+    pub fn nb_unique(&self) -> usize {
+        match self.mode {
+            Mode::Hash => self.hashes.len(),
+            Mode::Exact => self.sequences.len(),
+        }
+    }
+
+    /// Get number of records dropped
+    pub fn nb_duplicate(&self) -> u64 {
+        self.nb_duplicate.load(atomic::Ordering::Relaxed)
+    }
+}
+
+crate::fasta_sequential!(
+    FastaParser,
+    Dedup,
+    |record: block::Record, dedup: &mut Dedup| dedup.forward(record)
+);
+
+crate::fastq_sequential!(
+    FastqParser,
+    Dedup,
+    |record: block::Record, dedup: &mut Dedup| dedup.forward(record)
+);
+
+crate::fasta_sharedstate!(
+    FastaSharedState,
+    Dedup,
+    |record: block::Record, dedup: &Dedup| dedup.forward(record)
+);
+
+crate::fastq_sharedstate!(
+    FastqSharedState,
+    Dedup,
+    |record: block::Record, dedup: &Dedup| dedup.forward(record)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    fn duplicated_fastq() -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+
+        for (i, sequence) in ["ACGT", "TTTT", "ACGT", "GGCC", "TTTT", "ACGT", "AAAA"]
+            .iter()
+            .enumerate()
+        {
+            writeln!(file, "@{}\n{}\n+\n!!!!", i, sequence).unwrap();
+        }
+
+        file
+    }
+
+    fn collect(mode: Mode) -> (Dedup, std::sync::Arc<std::sync::Mutex<Vec<Vec<u8>>>>) {
+        let kept = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sender = kept.clone();
+
+        (
+            Dedup::new(mode, move |record: block::Record| {
+                sender.lock().unwrap().push(record.comment.to_vec())
+            }),
+            kept,
+        )
+    }
+
+    #[test]
+    fn sequential() {
+        let file = duplicated_fastq();
+
+        for mode in [Mode::Hash, Mode::Exact] {
+            let (mut dedup, kept) = collect(mode);
+
+            FastqParser::new().parse(file.path(), &mut dedup).unwrap();
+
+            assert_eq!(
+                *kept.lock().unwrap(),
+                vec![
+                    b"@0".to_vec(),
+                    b"@1".to_vec(),
+                    b"@3".to_vec(),
+                    b"@6".to_vec()
+                ]
+            );
+            assert_eq!(dedup.nb_unique(), 4);
+            assert_eq!(dedup.nb_duplicate(), 3);
+        }
+    }
+
+    #[test]
+    fn sharedstate() {
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let data = std::fs::read(file.path()).unwrap();
+        let doubled = tempfile::NamedTempFile::new().unwrap();
+        let mut output = doubled.reopen().unwrap();
+        output.write_all(&data).unwrap();
+        output.write_all(&data).unwrap();
+
+        let (dedup, kept) = collect(Mode::Exact);
+        FastqSharedState::new()
+            .parse(doubled.path(), &dedup)
+            .unwrap();
+
+        assert_eq!(kept.lock().unwrap().len(), 1_000);
+        assert_eq!(dedup.nb_unique(), 1_000);
+        assert_eq!(dedup.nb_duplicate(), 1_000);
+    }
+
+    #[test]
+    fn fasta() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b">1\nACGT\n>2\nACGT\n>3\nACGA\n").unwrap();
+
+        let (mut dedup, kept) = collect(Mode::Hash);
+        FastaParser::new().parse(file.path(), &mut dedup).unwrap();
+
+        assert_eq!(*kept.lock().unwrap(), vec![b">1".to_vec(), b">3".to_vec()]);
+        assert_eq!(dedup.mode(), Mode::Hash);
+    }
+}
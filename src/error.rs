@@ -1,3 +1,43 @@
+/// Number of bytes around offset of an error keep by [Error::with_context]
+pub const CONTEXT_LENGTH: usize = 32;
+
+/// Bytes of file around offset of an error, see [Error::with_context]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Bytes around offset of error
+    pub bytes: Vec<u8>,
+    /// Index of byte at offset of error in bytes
+    pub position: usize,
+    /// True if data continue before bytes
+    pub before: bool,
+    /// True if data continue after bytes
+    pub after: bool,
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.before {
+            std::write!(f, "...")?;
+        }
+
+        for byte in self.bytes.iter() {
+            match byte {
+                b'\n' => std::write!(f, "\\n")?,
+                b'\r' => std::write!(f, "\\r")?,
+                b'\t' => std::write!(f, "\\t")?,
+                b' '..=b'~' => std::write!(f, "{}", *byte as char)?,
+                _ => std::write!(f, "\\x{:02x}", byte)?,
+            }
+        }
+
+        if self.after {
+            std::write!(f, "...")?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("in_place_fastx failled to read file metadata {source}")]
@@ -6,20 +46,181 @@ pub enum Error {
     #[error("in_place_fastx can't open file {source}")]
     OpenFile { source: std::io::Error },
 
+    #[error("in_place_fastx can't read file {source}")]
+    ReadFile { source: std::io::Error },
+
+    #[error("in_place_fastx can't write file {source}")]
+    WriteFile { source: std::io::Error },
+
     #[error("in_place_fastx can't map file on memory {source}")]
     MapFile { source: std::io::Error },
 
-    #[error("in_place_fastx didn't find new line in block increase block size")]
-    NoNewLineInBlock,
+    #[error("in_place_fastx didn't find new line in block at offset {offset} increase block size")]
+    NoNewLineInBlock { offset: u64 },
+
+    #[error("in_place_fastx blocksize {blocksize} is too small to contain a record, use at least {min_needed}")]
+    BlocksizeTooSmall { blocksize: u64, min_needed: u64 },
+
+    #[error("Input file seems not be a fastq file, error at offset {offset}")]
+    NotAFastqFile { offset: u64 },
+
+    #[error("Input file seems not be a fasta file, error at offset {offset}")]
+    NotAFastaFile { offset: u64 },
+
+    #[error("in_place_fastx found a sequence line with inconsistent length at offset {offset}, file can't be index")]
+    InconsistentLineLength { offset: u64 },
+
+    #[error("in_place_fastx can't parse line {line} of fasta index")]
+    InvalidFai { line: usize },
+
+    #[error("in_place_fastx didn't find sequence {name} in fasta index")]
+    UnknownSequence { name: String },
+
+    #[error("in_place_fastx can't fetch region {start}..{end} of a sequence of length {length}")]
+    InvalidRegion { start: u64, end: u64, length: u64 },
+
+    #[error("Input file seems not be a fasta or a fastq file")]
+    UnknownFormat,
+
+    #[error("in_place_fastx can't read file compressed with {compression:?}")]
+    CompressedFile {
+        compression: crate::fastx::Compression,
+    },
+
+    #[error("in_place_fastx found a different number of records in paired files")]
+    UnpairedRecords,
+
+    #[error("in_place_fastx found a partial record at offset {offset}")]
+    PartialRecord { offset: u64 },
+
+    #[error("in_place_fastx found an incomplete record at offset {offset} at end of file, file seems truncated")]
+    TruncatedFile { offset: u64 },
+
+    #[error("in_place_fastx found a record at offset {offset} with sequence length {seq_len} and quality length {qual_len}")]
+    SeqQualLengthMismatch {
+        offset: u64,
+        seq_len: usize,
+        qual_len: usize,
+    },
+
+    #[error("in_place_fastx found a base {byte} not in alphabet at offset {offset}")]
+    InvalidBase { byte: u8, offset: u64 },
+
+    #[error("in_place_fastx found a quality {quality} lower than phred offset {phred_offset}")]
+    QualityBelowOffset { quality: u8, phred_offset: u8 },
+
+    #[error("in_place_fastx can't infer phred offset from quality range {min}..={max}")]
+    AmbiguousPhred { min: u8, max: u8 },
+
+    #[error("in_place_fastx parsing was cancelled")]
+    Cancelled,
+
+    #[error("in_place_fastx expected {expected} records but found {found}")]
+    RecordCountMismatch { expected: u64, found: u64 },
+
+    #[error("{source}, found: {context}")]
+    WithContext {
+        source: Box<Error>,
+        context: ErrorContext,
+    },
+
+    #[error("in_place_fastx worker failled {source}")]
+    Worker {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl Error {
+    /// Move offset of error by shift, used to convert an offset in block in an offset in file
+    pub fn add_offset(self, shift: u64) -> Self {
+        match self {
+            Error::NoNewLineInBlock { offset } => Error::NoNewLineInBlock {
+                offset: offset + shift,
+            },
+            Error::NotAFastqFile { offset } => Error::NotAFastqFile {
+                offset: offset + shift,
+            },
+            Error::NotAFastaFile { offset } => Error::NotAFastaFile {
+                offset: offset + shift,
+            },
+            Error::PartialRecord { offset } => Error::PartialRecord {
+                offset: offset + shift,
+            },
+            Error::InconsistentLineLength { offset } => Error::InconsistentLineLength {
+                offset: offset + shift,
+            },
+            Error::TruncatedFile { offset } => Error::TruncatedFile {
+                offset: offset + shift,
+            },
+            Error::InvalidBase { byte, offset } => Error::InvalidBase {
+                byte,
+                offset: offset + shift,
+            },
+            Error::SeqQualLengthMismatch {
+                offset,
+                seq_len,
+                qual_len,
+            } => Error::SeqQualLengthMismatch {
+                offset: offset + shift,
+                seq_len,
+                qual_len,
+            },
+            Error::WithContext { source, context } => Error::WithContext {
+                source: Box::new(source.add_offset(shift)),
+                context,
+            },
+            e => e,
+        }
+    }
+
+    /// Attach bytes of data around offset of error, data start at data_offset in file
+    ///
+    /// Error is unchanged if it isn't link to a position in data.
+    pub fn with_context(self, data: &[u8], data_offset: u64) -> Self {
+        let position = match self.offset() {
+            Some(offset) if offset >= data_offset && offset - data_offset <= data.len() as u64 => {
+                (offset - data_offset) as usize
+            }
+            _ => return self,
+        };
+
+        let begin = position.saturating_sub(CONTEXT_LENGTH / 2);
+        let end = (position + CONTEXT_LENGTH / 2).min(data.len());
 
-    #[error("Input file seems not be a fastq file")]
-    NotAFastqFile,
+        Error::WithContext {
+            source: Box::new(self),
+            context: ErrorContext {
+                bytes: data[begin..end].to_vec(),
+                position: position - begin,
+                before: begin != 0,
+                after: end != data.len(),
+            },
+        }
+    }
 
-    #[error("Input file seems not be a fasta file")]
-    NotAFastaFile,
+    /// Get bytes around error, if error was built by [Error::with_context]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
 
-    #[error("in_place_fastx found a partial record")]
-    PartialRecord,
+    /// Get offset in file where error occur, if error is link to a position
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Error::NoNewLineInBlock { offset }
+            | Error::NotAFastqFile { offset }
+            | Error::NotAFastaFile { offset }
+            | Error::PartialRecord { offset }
+            | Error::InconsistentLineLength { offset }
+            | Error::TruncatedFile { offset }
+            | Error::InvalidBase { offset, .. }
+            | Error::SeqQualLengthMismatch { offset, .. } => Some(*offset),
+            Error::WithContext { source, .. } => source.offset(),
+            _ => None,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
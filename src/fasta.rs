@@ -1,34 +1,73 @@
 //! Struct that extract part of file (called block) and read it as fasta file.
+//!
+//! Parsers of fasta file are build with [fasta_sequential](crate::fasta_sequential) and [fasta_sharedstate](crate::fasta_sharedstate), they call [impl_sequential](crate::impl_sequential) and [impl_sharedstate](crate::impl_sharedstate) with fasta [Producer] and [Reader].
 
 // #![feature(trace_macros)]
 
 // trace_macros!(true);
 
 /* crate use */
-use bstr::ByteSlice;
 
 /* project use */
 use crate::block;
 use crate::error;
 
-impl_producer!(Producer, |block: &[u8]| {
-    let mut end = block.len();
-
-    for _ in 0..2 {
-        end = block[..end]
-            .rfind_byte(b'\n')
-            .ok_or(error::Error::NoNewLineInBlock)?;
+/* mod declaration */
+pub mod index;
+
+impl_producer!(
+    Producer,
+    |block: &[u8]| {
+        // each new line is found once, from end to begin of block
+        let mut newlines = memchr::memrchr_iter(b'\n', block);
+        let mut end = block.len();
+
+        let mut nb_line = 0;
+        while nb_line < 2 {
+            end = newlines
+                .next()
+                .ok_or(error::Error::NoNewLineInBlock { offset: 0 })?;
+
+            match block.get(end + 1) {
+                Some(b'>') => return Ok((end + 1) as u64),
+                // new line at end of block, empty lines and comment lines of old fasta files aren't count
+                None | Some(b'\n') | Some(b';') => (),
+                _ => nb_line += 1,
+            }
+        }
 
-        if end + 1 < block.len() && block[end + 1] == b'>' {
-            return Ok((end + 1) as u64);
+        Err(error::Error::NotAFastaFile { offset: end as u64 })
+    },
+    find_record_start,
+    |data: &[u8]| {
+        // a record is incomplete if file end in its comment line
+        let end = data.len() - data.iter().rev().take_while(|c| **c == b'\n').count();
+        let last_line = memchr::memrchr(b'\n', &data[..end]).map_or(0, |pos| pos + 1);
+
+        if data.get(last_line) == Some(&b'>') {
+            Some(last_line)
+        } else {
+            None
         }
     }
+);
+
+/// Search the begin of the first record that follow a new line in data, a record start by a line begin by '>'
+///
+/// Data could start in the middle of a record, position 0 is never return.
+pub fn find_record_start(data: &[u8]) -> Option<usize> {
+    memchr::memchr_iter(b'\n', data)
+        .map(|pos| pos + 1)
+        .find(|start| data.get(*start) == Some(&b'>'))
+}
 
-    Err(error::Error::NotAFastaFile)
-});
+impl_chunked_producer!(ChunkedProducer, Producer);
+
+#[cfg(feature = "async-tokio")]
+impl_async_producer!(AsyncProducer, Producer);
 
 impl_reader!(Reader, |block: &'a block::Block, offset: &mut usize| {
-    if *offset == block.len() {
+    if *offset >= block.len() {
         Ok(None)
     } else {
         let comment = &block.data()[Self::get_line(block, offset)?];
@@ -37,8 +76,8 @@ impl_reader!(Reader, |block: &'a block::Block, offset: &mut usize| {
         let sequence = &block.data()[Self::get_line(block, offset)?];
         *offset += sequence.len() + 1;
 
-        let plus = &block.data()[*offset..*offset];
-        let quality = &block.data()[*offset..*offset];
+        let plus = &[];
+        let quality = &[];
 
         Ok(Some(crate::block::Record {
             comment,
@@ -49,10 +88,304 @@ impl_reader!(Reader, |block: &'a block::Block, offset: &mut usize| {
     }
 });
 
+impl_lenient_reader!(LenientReader, Reader, find_record_start);
+
+/// Write records in fasta format, sequence are wrap at line_width
+pub struct Writer<W>
+where
+    W: std::io::Write,
+{
+    inner: W,
+    line_width: usize,
+}
+
+impl<W> Writer<W>
+where
+    W: std::io::Write,
+{
+    /// Create a Writer that write each sequence on one line, output could be read by [Reader]
+    pub fn new(inner: W) -> Self {
+        Self::with_line_width(0, inner)
+    }
+
+    /// Create a Writer that wrap sequence at line_width bases, if line_width is 0 sequence isn't wrap
+    ///
+    /// [Reader] read one sequence line per record, a wrapped output can't be read by this crate.
+    pub fn with_line_width(line_width: usize, inner: W) -> Self {
+        Self { inner, line_width }
+    }
+
+    /// Write an [OwnedRecord](block::OwnedRecord)
+    pub fn write_record(&mut self, record: &block::OwnedRecord) -> error::Result<()> {
+        self.write(&record.as_record())
+    }
+
+    /// Write a [Record](block::Record), sigil of comment is replace by '>', new lines in sequence are remove before wrapping
+    pub fn write(&mut self, record: &block::Record) -> error::Result<()> {
+        let comment = match record.comment.first() {
+            Some(b'@') | Some(b'>') => &record.comment[1..],
+            _ => record.comment,
+        };
+
+        self.write_all(b">")?;
+        self.write_all(comment)?;
+        self.write_all(b"\n")?;
+
+        if self.line_width == 0 {
+            for line in record.sequence.split(|c| *c == b'\n') {
+                self.write_all(line)?;
+            }
+            self.write_all(b"\n")?;
+        } else {
+            let mut column = 0;
+            for line in record.sequence.split(|c| *c == b'\n') {
+                let mut line = line;
+                while !line.is_empty() {
+                    let len = (self.line_width - column).min(line.len());
+                    self.write_all(&line[..len])?;
+                    line = &line[len..];
+                    column += len;
+
+                    if column == self.line_width {
+                        self.write_all(b"\n")?;
+                        column = 0;
+                    }
+                }
+            }
+
+            if column != 0 || record.sequence.is_empty() {
+                self.write_all(b"\n")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get underlying writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> error::Result<()> {
+        self.inner
+            .write_all(data)
+            .map_err(|source| error::Error::WriteFile { source })
+    }
+}
+
+/// Build a Block producer of fasta records read in reader, like stdin or a pipe, blocks could only be read sequentially
+pub fn from_reader<R>(reader: R) -> crate::stream::Producer
+where
+    R: std::io::Read + Send + 'static,
+{
+    crate::stream::Producer::with_format(
+        crate::fastx::Format::Fasta,
+        crate::DEFAULT_BLOCKSIZE,
+        reader,
+    )
+}
+
+/// Call f on each record of fasta file, records are read sequentially
+pub fn for_each<P, F>(path: P, mut f: F) -> error::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    F: FnMut(block::Record),
+{
+    try_for_each(path, |record| {
+        f(record);
+        Ok(())
+    })
+}
+
+/// Call f on each record of fasta file, records are read sequentially, stop at first error
+pub fn try_for_each<P, F>(path: P, mut f: F) -> error::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    F: FnMut(block::Record) -> error::Result<()>,
+{
+    for block in Producer::new(path)? {
+        let mut reader = Reader::new(block?);
+
+        while let Some(record) = reader.next_record()? {
+            f(record)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Call f on each record of fasta file until f return Some, records are read sequentially, reading stop at first Some or first error
+pub fn find_map<P, R, F>(path: P, mut f: F) -> error::Result<Option<R>>
+where
+    P: AsRef<std::path::Path>,
+    F: FnMut(block::Record) -> Option<R>,
+{
+    for block in Producer::new(path)? {
+        let mut reader = Reader::new(block?);
+
+        while let Some(record) = reader.next_record()? {
+            if let Some(result) = f(record) {
+                return Ok(Some(result));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Call f on records of fasta file until f return Some, blocks are read in parallel
+///
+/// Result could come from any record for which f return Some, not necessarily the first one in file, parsing stop soon after a Some or an error.
+pub fn par_find_map<P, R, F>(path: P, f: F) -> error::Result<Option<R>>
+where
+    P: AsRef<std::path::Path>,
+    R: Send,
+    F: Fn(block::Record) -> Option<R> + Sync + Send,
+{
+    use rayon::iter::ParallelBridge;
+    use rayon::iter::ParallelIterator;
+
+    Producer::new(path)?
+        .par_bridge()
+        .map(|block| {
+            let mut reader = Reader::new(block?);
+
+            while let Some(record) = reader.next_record()? {
+                if let Some(result) = f(record) {
+                    return Ok(Some(result));
+                }
+            }
+
+            Ok(None)
+        })
+        .find_map_any(|result| match result {
+            Ok(None) => None,
+            found => Some(found),
+        })
+        .unwrap_or(Ok(None))
+}
+
+/// Build samtools compatible [index](index::FaiIndex) of fasta file
+pub fn faidx<P>(path: P) -> error::Result<index::FaiIndex>
+where
+    P: AsRef<std::path::Path>,
+{
+    index::FaiIndex::build(path)
+}
+
+/// Parse fasta file in parallel, each thread fold records in is own accumulator build by init, accumulators are merged by merge
+pub fn parse_fold<P, A, I, F, M>(path: P, init: I, fold: F, merge: M) -> error::Result<A>
+where
+    P: AsRef<std::path::Path>,
+    A: Send,
+    I: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, block::Record) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    crate::parser::fold::fold(Producer::new(path)?, Reader::read_record, init, fold, merge)
+}
+
+/// Same as [parse_fold] but fold get all records of a block at once, see [fold_batch](crate::parser::fold::fold_batch)
+pub fn parse_fold_batch<P, A, I, F, M>(path: P, init: I, fold: F, merge: M) -> error::Result<A>
+where
+    P: AsRef<std::path::Path>,
+    A: Send,
+    I: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, &[block::Record]) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    crate::parser::fold::fold_batch(Producer::new(path)?, Reader::read_record, init, fold, merge)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn for_each() {
+        let mut nb_base = 0;
+        super::for_each(crate::tests::generate_fasta(42, 1_000, 150), |record| {
+            nb_base += record.sequence.len()
+        })
+        .unwrap();
+
+        assert_eq!(nb_base, 150_000);
+    }
+
+    #[test]
+    fn find_map() {
+        let file = crate::tests::generate_fasta(42, 1_000, 150);
+
+        assert_eq!(
+            super::find_map(file.path(), |record| (record.id() == b"10")
+                .then(|| record.sequence.len()))
+            .unwrap(),
+            Some(150)
+        );
+        assert_eq!(
+            super::par_find_map(file.path(), |record| (record.id() == b"999")
+                .then(|| record.sequence.len()))
+            .unwrap(),
+            Some(150)
+        );
+        assert_eq!(
+            super::par_find_map(file.path(), |record| (record.id() == b"1000").then(|| ()))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn writer() {
+        let record = block::OwnedRecord {
+            comment: b">1 desc".to_vec(),
+            sequence: b"ACGTA\nCGTACG\nT".to_vec(),
+            plus: Vec::new(),
+            quality: Vec::new(),
+        };
+
+        let mut writer = Writer::with_line_width(4, Vec::new());
+        writer.write_record(&record).unwrap();
+        writer
+            .write(&block::Record {
+                comment: b"@2",
+                sequence: b"ACGT",
+                plus: b"+",
+                quality: b"!!!!",
+            })
+            .unwrap();
+        assert_eq!(
+            writer.into_inner(),
+            b">1 desc\nACGT\nACGT\nACGT\n>2\nACGT\n".to_vec()
+        );
+
+        let mut writer = Writer::with_line_width(0, Vec::new());
+        writer.write_record(&record).unwrap();
+        assert_eq!(writer.into_inner(), b">1 desc\nACGTACGTACGT\n".to_vec());
+
+        let mut writer = Writer::new(Vec::new());
+        writer.write_record(&block::OwnedRecord::default()).unwrap();
+        assert_eq!(writer.into_inner(), b">\n\n".to_vec());
+    }
+
+    #[test]
+    fn writer_round_trip() {
+        let file = crate::tests::generate_fasta(42, 100, 150);
+
+        let mut writer = Writer::new(Vec::new());
+        crate::fasta::try_for_each(file.path(), |record| writer.write(&record)).unwrap();
+        let data = writer.into_inner();
+        assert_eq!(data, std::fs::read(file.path()).unwrap());
+
+        let mut reader = Reader::new_validated(block::Block::from_vec(0, data));
+        let mut nb_record = 0;
+        while let Some(record) = reader.next_record().unwrap() {
+            assert_eq!(record.sequence.len(), 150);
+            nb_record += 1;
+        }
+        assert_eq!(nb_record, 100);
+    }
+
     mod producer {
         use super::*;
 
@@ -91,6 +424,155 @@ GGACGCTATTACTCGCCGGTGAGGCGGTCTTCCTTGACTATACCGATCGTGGAGTTCATGCGCGCGGATCCCTCAGCGTT
             );
         }
 
+        #[cfg(unix)]
+        #[test]
+        fn advise() {
+            let mut tmp = Producer::new(crate::tests::generate_fasta(42, 1_000, 150)).unwrap();
+
+            tmp.advise(memmap2::Advice::WillNeed).unwrap();
+
+            let mut block_length = Vec::new();
+            while let Ok(Some(block)) = tmp.next_block() {
+                block_length.push(block.len());
+            }
+
+            assert_eq!(block_length, vec![65410, 65520, 24960]);
+        }
+
+        #[test]
+        fn without_mmap() {
+            let mut tmp = Producer::new(crate::tests::generate_fasta(42, 1_000, 150)).unwrap();
+            tmp.mem = None;
+
+            let mut block_length = Vec::new();
+            let mut block_offset = Vec::new();
+            while let Ok(Some(block)) = tmp.next_block() {
+                block_length.push(block.len());
+                block_offset.push(block.file_offset());
+            }
+
+            assert_eq!(block_length, vec![65410, 65520, 24960]);
+            assert_eq!(block_offset, vec![0, 65410, 130930]);
+        }
+
+        #[test]
+        fn seek_to() {
+            let file = crate::tests::generate_fasta(42, 1_000, 150);
+            let mut producer = Producer::new(file.path()).unwrap();
+
+            producer.seek_to(10).unwrap();
+            assert_eq!(producer.position(), 154);
+
+            producer.seek_to(154).unwrap();
+            assert_eq!(producer.position(), 154);
+
+            let block = producer.next_block().unwrap().unwrap();
+            assert_eq!(&block.data()[..3], b">1\n");
+
+            producer.seek_to(u64::MAX).unwrap();
+            assert_eq!(producer.position(), producer.file_length());
+            assert!(producer.next_block().unwrap().is_none());
+        }
+
+        #[test]
+        fn find_record_start() {
+            assert_eq!(Producer::find_record_start(b"ACGT\n>1\nACGT\n"), Some(5));
+            assert_eq!(Producer::find_record_start(b">1\nACGT\n"), None);
+            assert_eq!(
+                crate::fasta::find_record_start(b"CGT\n>2\nA\n>3\n"),
+                Some(4)
+            );
+        }
+
+        #[test]
+        fn chunked() {
+            let file = crate::tests::generate_fasta(42, 1_000, 150);
+
+            let producer = ChunkedProducer::with_threads(7, file.path()).unwrap();
+            let blocks = producer
+                .collect::<error::Result<Vec<block::Block>>>()
+                .unwrap();
+
+            assert_eq!(blocks.len(), 7);
+            assert_eq!(blocks[0].file_offset(), 0);
+            for pair in blocks.windows(2) {
+                assert_eq!(
+                    pair[0].file_offset() + pair[0].len() as u64,
+                    pair[1].file_offset()
+                );
+                assert_eq!(pair[1].data()[0], b'>');
+            }
+
+            let mut nb_record = 0;
+            for block in blocks {
+                let mut reader = Reader::new(block);
+                while reader.next_record().unwrap().is_some() {
+                    nb_record += 1;
+                }
+            }
+            assert_eq!(nb_record, 1_000);
+        }
+
+        #[test]
+        fn blocksize_too_small() {
+            let mut producer =
+                Producer::with_blocksize(100, crate::tests::generate_fasta(42, 1_000, 150))
+                    .unwrap();
+
+            assert!(matches!(
+                producer.next_block(),
+                Err(error::Error::BlocksizeTooSmall {
+                    blocksize: 100,
+                    min_needed: 200
+                })
+            ));
+
+            let mut producer =
+                Producer::with_blocksize(200, crate::tests::generate_fasta(42, 1_000, 150))
+                    .unwrap();
+            assert!(producer.next_block().unwrap().is_some());
+        }
+
+        #[test]
+        fn empty_file() {
+            use std::io::Write;
+
+            for content in [&b""[..], b"\n", b"\r\n\n"] {
+                let mut file = tempfile::NamedTempFile::new().unwrap();
+                file.write_all(content).unwrap();
+
+                let mut producer = Producer::new(file.path()).unwrap();
+                assert!(producer.next_block().unwrap().is_none());
+                assert!(producer.next_block().unwrap().is_none());
+            }
+
+            let mut producer = Producer::from_vec(1, Vec::new());
+            assert_eq!(producer.blocksize(), 0);
+            assert!(producer.next_block().unwrap().is_none());
+
+            let mut producer = Producer::from_vec(1_000, b">1\nACGT\n\n\n".to_vec());
+            assert!(producer.next_block().unwrap().is_some());
+            assert!(producer.next_block().unwrap().is_none());
+        }
+
+        #[test]
+        fn truncated_file() {
+            let mut producer = Producer::from_vec(1_000, b">1\nACGT\n>2\nTG\n>3 desc".to_vec());
+            assert!(matches!(
+                producer.next_block(),
+                Err(error::Error::TruncatedFile { offset: 14 })
+            ));
+
+            let mut producer = Producer::from_vec(1_000, b">1\nACGT\n>2\n\n".to_vec());
+            assert!(matches!(
+                producer.next_block(),
+                Err(error::Error::TruncatedFile { offset: 8 })
+            ));
+
+            assert_eq!(Producer::truncated_record(b">1\nACGT\n>2\nTG"), None);
+            assert_eq!(Producer::truncated_record(b">1\nACGT\n"), None);
+        }
+
         #[test]
         fn with_blocksize_buffer_larger_file() {
             let mut tmp =
@@ -101,6 +583,50 @@ GGACGCTATTACTCGCCGGTGAGGCGGTCTTCCTTGACTATACCGATCGTGGAGTTCATGCGCGCGGATCCCTCAGCGTT
             assert_eq!(block.len(), 308);
         }
 
+        #[test]
+        fn blocksize_around_file_length() {
+            let file = crate::tests::generate_fasta(42, 10, 150);
+            let length = std::fs::metadata(file.path()).unwrap().len();
+
+            for blocksize in [length - 1, length, length + 1] {
+                let mut producer = Producer::with_blocksize(blocksize, file.path()).unwrap();
+
+                let mut nb_record = 0;
+                while let Some(block) = producer.next_block().unwrap() {
+                    let mut reader = Reader::new_validated(block);
+                    while reader.next_record().unwrap().is_some() {
+                        nb_record += 1;
+                    }
+                }
+
+                assert_eq!(nb_record, 10);
+            }
+        }
+
+        #[test]
+        fn blocksize_on_record_boundary() {
+            let file = crate::tests::generate_fasta(42, 10, 150);
+            let data = std::fs::read(file.path()).unwrap();
+
+            // records have same length, blocksize is a multiple of record length
+            // end of a record is found with begin of next record, a block must contains two records
+            let record_length = (data.len() / 10) as u64;
+            for blocksize in [record_length * 2, record_length * 5, record_length * 10] {
+                let mut producer = Producer::with_blocksize(blocksize, file.path()).unwrap();
+
+                let mut nb_record = 0;
+                while let Some(block) = producer.next_block().unwrap() {
+                    let mut reader = Reader::new_validated(block);
+                    while reader.next_record().unwrap().is_some() {
+                        nb_record += 1;
+                    }
+                }
+
+                assert_eq!(nb_record, 10);
+                assert_eq!(producer.offset(), data.len() as u64);
+            }
+        }
+
         #[test]
         fn get_all_block() {
             let mut tmp = Producer::new(crate::tests::generate_fasta(42, 1_000, 150)).unwrap();
@@ -186,6 +712,103 @@ Praesent porta sapien id tortor hendrerit, a hendrerit dolor commodo. Donec sed
     mod reader {
         use super::*;
 
+        #[test]
+        fn skip_blank_lines() {
+            use std::io::Write;
+
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(b">1\nACGT\n\n>2\nTGCA\n\n\n>3\nAAAA\n\n")
+                .unwrap();
+
+            let mut producer = Producer::with_blocksize(12, file.path()).unwrap();
+
+            let mut sequences = Vec::new();
+            while let Some(block) = producer.next_block().unwrap() {
+                let mut reader = Reader::new(block);
+                reader.set_skip_blank_lines(true);
+                reader
+                    .for_each(|record| sequences.push(record.sequence.to_vec()))
+                    .unwrap();
+            }
+
+            assert_eq!(
+                sequences,
+                vec![b"ACGT".to_vec(), b"TGCA".to_vec(), b"AAAA".to_vec()]
+            );
+        }
+
+        #[test]
+        fn skip_comment_lines() {
+            let data = b";old comment\n>1\nACGT\n;comment\n\n;other\n>2\nTGCA\n;last";
+
+            let mut producer = Producer::from_vec(30, data.to_vec());
+            let mut sequences = Vec::new();
+            while let Some(block) = producer.next_block().unwrap() {
+                let mut reader = Reader::new(block);
+                reader.set_skip_comment_lines(true);
+                reader.set_skip_blank_lines(true);
+                reader
+                    .for_each(|record| sequences.push(record.sequence.to_vec()))
+                    .unwrap();
+            }
+            assert_eq!(sequences, vec![b"ACGT".to_vec(), b"TGCA".to_vec()]);
+
+            let block = block::Block::from_slice(data);
+
+            let mut reader = Reader::new(block.clone());
+            reader.set_skip_comment_lines(true);
+            reader.set_skip_blank_lines(true);
+
+            let mut records = Vec::new();
+            reader
+                .for_each(|record| {
+                    records.push((record.comment.to_vec(), record.sequence.to_vec()))
+                })
+                .unwrap();
+            assert_eq!(
+                records,
+                vec![
+                    (b">1".to_vec(), b"ACGT".to_vec()),
+                    (b">2".to_vec(), b"TGCA".to_vec())
+                ]
+            );
+
+            let mut reader = Reader::new(block);
+            assert_eq!(
+                reader.next_record().unwrap().unwrap().comment,
+                b";old comment"
+            );
+        }
+
+        #[test]
+        fn lenient() {
+            let mut reader =
+                LenientReader::new(block::Block::from_slice(b">1\nACGT\n>2\nTGCA\n>3"));
+
+            assert_eq!(reader.next_record().unwrap().sequence, b"ACGT");
+            assert_eq!(reader.next_record().unwrap().sequence, b"TGCA");
+            assert!(reader.next_record().is_none());
+            assert_eq!(reader.skipped(), &[std::ops::Range { start: 16, end: 18 }]);
+        }
+
+        #[test]
+        fn no_trailing_newline() {
+            let mut reader = Reader::new(block::Block::from_slice(b">1\nACGT\n>2\nTGCA"));
+
+            assert_eq!(reader.next_record().unwrap().unwrap().sequence, b"ACGT");
+            let record = reader.next_record().unwrap().unwrap();
+            assert_eq!(record.comment, b">2");
+            assert_eq!(record.sequence, b"TGCA");
+            assert!(reader.next_record().unwrap().is_none());
+
+            let mut reader = Reader::new(block::Block::from_slice(b">1\nACGT\n>2"));
+            assert!(reader.next_record().unwrap().is_some());
+            assert!(matches!(
+                reader.next_record(),
+                Err(error::Error::PartialRecord { offset: 10 })
+            ));
+        }
+
         #[test]
         fn iterate_over_seq() {
             let mut producer =
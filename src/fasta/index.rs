@@ -0,0 +1,486 @@
+//! Build and write samtools compatible index (.fai) of a fasta file, multi-line sequences are supported.
+
+/* std use */
+use std::io::Write;
+
+/* crate use */
+
+/* project use */
+use crate::error;
+use crate::fasta;
+
+/// Index information of one sequence
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaiRecord {
+    /// Name of sequence, bytes between '>' and first ASCII whitespace
+    pub name: Vec<u8>,
+    /// Number of bases in sequence
+    pub length: u64,
+    /// Offset in file of first base
+    pub offset: u64,
+    /// Number of bases per line
+    pub line_bases: u64,
+    /// Number of bytes per line, new line included
+    pub line_width: u64,
+}
+
+/// Index of a fasta file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FaiIndex {
+    records: Vec<FaiRecord>,
+}
+
+impl FaiIndex {
+    /// Scan fasta file once to build index, a [UTF-8 byte order mark](crate::block::UTF8_BOM) at begin of file is skipped
+    pub fn build<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let producer = fasta::Producer::with_blocksize(u64::MAX, path)?;
+        let begin = producer.offset();
+        let block = producer.block_range(begin, producer.file_length())?;
+
+        let mut index = Self::from_data(block.data()).map_err(|e| e.add_offset(begin))?;
+        for record in index.records.iter_mut() {
+            record.offset += begin;
+        }
+
+        Ok(index)
+    }
+
+    /// Build index of fasta data
+    pub fn from_data(data: &[u8]) -> error::Result<Self> {
+        let mut records: Vec<FaiRecord> = Vec::new();
+        let mut short_line = false;
+
+        let mut begin = 0;
+        while begin < data.len() {
+            let end = memchr::memchr(b'\n', &data[begin..])
+                .map(|pos| begin + pos)
+                .unwrap_or_else(|| data.len());
+            let line = &data[begin..end];
+
+            if line.first() == Some(&b'>') {
+                let name = &line[1..];
+                let name = match name.iter().position(|c| c.is_ascii_whitespace()) {
+                    Some(pos) => &name[..pos],
+                    None => name,
+                };
+
+                records.push(FaiRecord {
+                    name: name.to_vec(),
+                    offset: (end + 1) as u64,
+                    ..Default::default()
+                });
+                short_line = false;
+            } else {
+                let record = records.last_mut().ok_or(error::Error::NotAFastaFile {
+                    offset: begin as u64,
+                })?;
+
+                let bases = match line.last() {
+                    Some(b'\r') => line.len() - 1,
+                    _ => line.len(),
+                } as u64;
+                let width = (end + 1 - begin) as u64;
+
+                // after a short or blank line only blank lines are allowed
+                if short_line || (record.line_bases != 0 && bases > record.line_bases) {
+                    if bases != 0 {
+                        return Err(error::Error::InconsistentLineLength {
+                            offset: begin as u64,
+                        });
+                    }
+                } else if record.line_bases == 0 {
+                    if bases == 0 {
+                        short_line = true;
+                    } else {
+                        record.line_bases = bases;
+                        record.line_width = width;
+                    }
+                } else if bases < record.line_bases {
+                    short_line = true;
+                }
+
+                record.length += bases;
+            }
+
+            begin = end + 1;
+        }
+
+        Ok(Self { records })
+    }
+
+    /// Read index in samtools format, a line with bases but no bases per line is an [InvalidFai](error::Error::InvalidFai)
+    pub fn read<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let data = std::fs::read(path).map_err(|source| error::Error::ReadFile { source })?;
+
+        let mut records = Vec::new();
+        for (number, line) in data.split(|c| *c == b'\n').enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut fields = line.split(|c| *c == b'\t');
+            let name = fields.next().unwrap_or(&[]).to_vec();
+            let mut values = [0; 4];
+            for value in values.iter_mut() {
+                *value = fields
+                    .next()
+                    .and_then(|field| std::str::from_utf8(field).ok())
+                    .and_then(|field| field.trim_end().parse::<u64>().ok())
+                    .ok_or(error::Error::InvalidFai { line: number + 1 })?;
+            }
+
+            // a sequence without bases per line can't be fetch
+            if values[2] == 0 && values[0] > 0 {
+                return Err(error::Error::InvalidFai { line: number + 1 });
+            }
+
+            records.push(FaiRecord {
+                name,
+                length: values[0],
+                offset: values[1],
+                line_bases: values[2],
+                line_width: values[3],
+            });
+        }
+
+        Ok(Self { records })
+    }
+
+    /// Get index information of each sequence
+    pub fn records(&self) -> &[FaiRecord] {
+        &self.records
+    }
+
+    /// Get index information of sequence name
+    pub fn get(&self, name: &[u8]) -> Option<&FaiRecord> {
+        self.records.iter().find(|record| record.name == name)
+    }
+
+    /// Write index in samtools format
+    pub fn write<P>(&self, path: P) -> error::Result<()>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let file =
+            std::fs::File::create(path).map_err(|source| error::Error::OpenFile { source })?;
+        let mut output = std::io::BufWriter::new(file);
+
+        for record in &self.records {
+            output
+                .write_all(&record.name)
+                .and_then(|_| {
+                    std::writeln!(
+                        output,
+                        "\t{}\t{}\t{}\t{}",
+                        record.length,
+                        record.offset,
+                        record.line_bases,
+                        record.line_width
+                    )
+                })
+                .map_err(|source| error::Error::WriteFile { source })?;
+        }
+
+        output
+            .flush()
+            .map_err(|source| error::Error::WriteFile { source })
+    }
+}
+
+/// Read sequence, or part of sequence, of an indexed fasta file without reading whole file
+pub struct IndexedFastaReader {
+    producer: fasta::Producer,
+    index: FaiIndex,
+}
+
+impl IndexedFastaReader {
+    /// Open fasta file and read its index, file is map once, only pages of fetched sequences are read
+    pub fn open<P, I>(fasta: P, fai: I) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+        I: AsRef<std::path::Path>,
+    {
+        Ok(Self {
+            producer: fasta::Producer::new(fasta)?,
+            index: FaiIndex::read(fai)?,
+        })
+    }
+
+    /// Get index of fasta file
+    pub fn index(&self) -> &FaiIndex {
+        &self.index
+    }
+
+    /// Get record of sequence name, new lines are remove from sequence
+    pub fn fetch(&self, name: &str) -> error::Result<crate::block::OwnedRecord> {
+        let record = self.record(name)?;
+
+        Ok(crate::block::OwnedRecord {
+            comment: self.comment(record.offset)?,
+            sequence: self.fetch_region(name, 0, record.length)?,
+            plus: Vec::new(),
+            quality: Vec::new(),
+        })
+    }
+
+    /// Get bases start..end, 0-based, of sequence name, new lines are remove
+    pub fn fetch_region(&self, name: &str, start: u64, end: u64) -> error::Result<Vec<u8>> {
+        let record = self.record(name)?;
+
+        if start > end || end > record.length {
+            return Err(error::Error::InvalidRegion {
+                start,
+                end,
+                length: record.length,
+            });
+        }
+
+        let position = |base: u64| {
+            record.offset
+                + (base / record.line_bases) * record.line_width
+                + base % record.line_bases
+        };
+        let (begin, end) = if start == end {
+            (0, 0)
+        } else {
+            (position(start), position(end - 1) + 1)
+        };
+
+        let block = self.producer.block_range(begin, end)?;
+
+        Ok(block
+            .data()
+            .iter()
+            .filter(|c| **c != b'\n' && **c != b'\r')
+            .copied()
+            .collect())
+    }
+
+    fn record(&self, name: &str) -> error::Result<&FaiRecord> {
+        self.index
+            .get(name.as_bytes())
+            .ok_or_else(|| error::Error::UnknownSequence {
+                name: name.to_string(),
+            })
+    }
+
+    /// Get comment line that end just before offset, window is double until begin of line is found
+    fn comment(&self, offset: u64) -> error::Result<Vec<u8>> {
+        let end = offset.min(self.producer.file_length());
+        let mut length = 256;
+
+        loop {
+            // a byte order mark is skipped by producer
+            let begin = end.saturating_sub(length).max(self.producer.offset());
+            let block = self.producer.block_range(begin, end)?;
+            let data = block.data();
+            let line = match data.last() {
+                Some(b'\n') => &data[..data.len() - 1],
+                _ => data,
+            };
+
+            match memchr::memrchr(b'\n', line) {
+                Some(pos) => return Ok(trim_cr(&line[pos + 1..]).to_vec()),
+                None if begin == self.producer.offset() => return Ok(trim_cr(line).to_vec()),
+                None => length *= 2,
+            }
+        }
+    }
+}
+
+fn trim_cr(line: &[u8]) -> &[u8] {
+    match line.last() {
+        Some(b'\r') => &line[..line.len() - 1],
+        _ => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_line() {
+        let index =
+            FaiIndex::from_data(b">1 desc\nACGT\nACGT\nAC\n>2\nACG\n>3\r\nACGT\r\nAC\r\n").unwrap();
+
+        assert_eq!(
+            index.records(),
+            &[
+                FaiRecord {
+                    name: b"1".to_vec(),
+                    length: 10,
+                    offset: 8,
+                    line_bases: 4,
+                    line_width: 5,
+                },
+                FaiRecord {
+                    name: b"2".to_vec(),
+                    length: 3,
+                    offset: 24,
+                    line_bases: 3,
+                    line_width: 4,
+                },
+                FaiRecord {
+                    name: b"3".to_vec(),
+                    length: 6,
+                    offset: 32,
+                    line_bases: 4,
+                    line_width: 6,
+                },
+            ]
+        );
+        assert_eq!(index.get(b"2").unwrap().length, 3);
+        assert!(index.get(b"4").is_none());
+
+        let index = FaiIndex::from_data(b">1\n\n>2\nACGT\nAC\n\n").unwrap();
+        assert_eq!(index.get(b"1").unwrap().length, 0);
+        assert_eq!(index.get(b"2").unwrap().length, 6);
+    }
+
+    #[test]
+    fn inconsistent_line() {
+        assert!(matches!(
+            FaiIndex::from_data(b">1\nACGT\nAC\nACGT\n"),
+            Err(error::Error::InconsistentLineLength { offset: 11 })
+        ));
+        assert!(matches!(
+            FaiIndex::from_data(b">1\nACGT\nACGTA\n"),
+            Err(error::Error::InconsistentLineLength { offset: 8 })
+        ));
+        assert!(matches!(
+            FaiIndex::from_data(b">1\n\nACGT\n"),
+            Err(error::Error::InconsistentLineLength { offset: 4 })
+        ));
+        assert!(matches!(
+            FaiIndex::from_data(b"ACGT\n>1\nACGT\n"),
+            Err(error::Error::NotAFastaFile { offset: 0 })
+        ));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let fasta = tempfile::NamedTempFile::new().unwrap();
+        let mut data = crate::block::UTF8_BOM.to_vec();
+        data.extend_from_slice(b">1\nACGT\nAC\n>2\nACG\n");
+        std::fs::write(fasta.path(), data).unwrap();
+
+        let index = FaiIndex::build(fasta.path()).unwrap();
+        assert_eq!(
+            index.records(),
+            &[
+                FaiRecord {
+                    name: b"1".to_vec(),
+                    length: 6,
+                    offset: 6,
+                    line_bases: 4,
+                    line_width: 5,
+                },
+                FaiRecord {
+                    name: b"2".to_vec(),
+                    length: 3,
+                    offset: 17,
+                    line_bases: 3,
+                    line_width: 4,
+                },
+            ]
+        );
+
+        let fai = tempfile::NamedTempFile::new().unwrap();
+        index.write(fai.path()).unwrap();
+        let reader = IndexedFastaReader::open(fasta.path(), fai.path()).unwrap();
+        assert_eq!(reader.fetch("1").unwrap().comment, b">1".to_vec());
+        assert_eq!(reader.fetch_region("2", 0, 3).unwrap(), b"ACG".to_vec());
+    }
+
+    #[test]
+    fn write() {
+        let file = crate::tests::generate_fasta(42, 3, 150);
+        let index = FaiIndex::build(file.path()).unwrap();
+
+        let output = tempfile::NamedTempFile::new().unwrap();
+        index.write(output.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(output.path()).unwrap(),
+            "0\t150\t3\t150\t151\n1\t150\t157\t150\t151\n2\t150\t311\t150\t151\n"
+        );
+    }
+
+    #[test]
+    fn read() {
+        let index = FaiIndex::from_data(b">1 desc\nACGT\nACGT\nAC\n>2\nACG\n").unwrap();
+
+        let output = tempfile::NamedTempFile::new().unwrap();
+        index.write(output.path()).unwrap();
+
+        assert_eq!(FaiIndex::read(output.path()).unwrap(), index);
+
+        std::fs::write(output.path(), b"1\t10\t8\t4\t5\n2\t3\tA\t3\t4\n").unwrap();
+        assert!(matches!(
+            FaiIndex::read(output.path()),
+            Err(error::Error::InvalidFai { line: 2 })
+        ));
+
+        std::fs::write(output.path(), b"1\t4\t3\t0\t0\n").unwrap();
+        assert!(matches!(
+            FaiIndex::read(output.path()),
+            Err(error::Error::InvalidFai { line: 1 })
+        ));
+
+        std::fs::write(output.path(), b"1\t0\t3\t0\t0\n").unwrap();
+        assert_eq!(
+            FaiIndex::read(output.path()).unwrap().records()[0].length,
+            0
+        );
+    }
+
+    #[test]
+    fn fetch() {
+        let fasta = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            fasta.path(),
+            b">1 desc\nACGT\nTGCA\nAC\n>2\nACG\n>3\r\nACGT\r\nAC\r\n",
+        )
+        .unwrap();
+        let fai = tempfile::NamedTempFile::new().unwrap();
+        FaiIndex::build(fasta.path())
+            .unwrap()
+            .write(fai.path())
+            .unwrap();
+
+        let reader = IndexedFastaReader::open(fasta.path(), fai.path()).unwrap();
+
+        let record = reader.fetch("1").unwrap();
+        assert_eq!(record.comment, b">1 desc".to_vec());
+        assert_eq!(record.sequence, b"ACGTTGCAAC".to_vec());
+
+        let record = reader.fetch("3").unwrap();
+        assert_eq!(record.comment, b">3".to_vec());
+        assert_eq!(record.sequence, b"ACGTAC".to_vec());
+
+        assert_eq!(reader.fetch_region("1", 2, 7).unwrap(), b"GTTGC".to_vec());
+        assert_eq!(reader.fetch_region("1", 4, 8).unwrap(), b"TGCA".to_vec());
+        assert_eq!(reader.fetch_region("2", 1, 1).unwrap(), b"".to_vec());
+        assert_eq!(reader.fetch_region("3", 3, 6).unwrap(), b"TAC".to_vec());
+
+        assert!(matches!(
+            reader.fetch_region("2", 1, 4),
+            Err(error::Error::InvalidRegion {
+                start: 1,
+                end: 4,
+                length: 3
+            })
+        ));
+        assert!(matches!(
+            reader.fetch("4"),
+            Err(error::Error::UnknownSequence { .. })
+        ));
+    }
+}
@@ -1,78 +1,764 @@
 //! Struct that extract part of file (called block) and read it as fastq file.
+//!
+//! Parsers of fastq file are build with [fastq_sequential](crate::fastq_sequential) and [fastq_sharedstate](crate::fastq_sharedstate), they call [impl_sequential](crate::impl_sequential) and [impl_sharedstate](crate::impl_sharedstate) with fastq [Producer] and [Reader].
 
-use bstr::ByteSlice;
 /* crate use */
 
 /* project use */
 use crate::block;
 use crate::error;
 
-impl_producer!(Producer, |block: &[u8]| {
-    let mut end = block.len();
-
-    for _ in 0..5 {
-        end = block[..end]
-            .rfind_byte(b'\n')
-            .ok_or(error::Error::NoNewLineInBlock)?;
-
-        if end + 1 < block.len() && block[end + 1] == b'@' {
-            let prev = block[..end]
-                .rfind_byte(b'\n')
-                .ok_or(error::Error::NoNewLineInBlock)?;
-            if block[prev + 1] == b'+' {
-                let prevprev = block[..prev]
-                    .rfind_byte(b'\n')
-                    .ok_or(error::Error::NoNewLineInBlock)?;
-                if block[prevprev + 1] == b'+' {
-                    return Ok((end + 1) as u64);
-                } else {
-                    let prevprevprev = block[..prevprev]
-                        .rfind_byte(b'\n')
-                        .ok_or(error::Error::NoNewLineInBlock)?;
-                    if block[prevprevprev + 1] == b'@' {
-                        return Ok((prevprevprev + 1) as u64);
-                    } else {
-                        return Err(error::Error::NotAFastqFile);
-                    }
+/* mod declaration */
+pub mod interleaved;
+pub mod paired;
+pub mod trim;
+
+impl_producer!(
+    Producer,
+    |block: &[u8]| correct_block_size_with(block, &FASTQ),
+    find_record_start,
+    |data: &[u8]| truncated_record_with(data, &FASTQ)
+);
+
+/// First byte of header and separator lines of a fastq like format, records have four lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sigils {
+    pub header: u8,
+    pub separator: u8,
+}
+
+/// Sigils of fastq format
+pub const FASTQ: Sigils = Sigils {
+    header: b'@',
+    separator: b'+',
+};
+
+/// Build a producer of a fastq like format with other header and separator sigils, records are read by [Reader](crate::fastq::Reader)
+///
+/// Like other producer macros, expansion use `block`, `error`, `memchr` and `log` paths.
+#[macro_export(local_inner_macros)]
+macro_rules! fastq_like_producer {
+    ($name:ident, $header:expr, $separator:expr) => {
+        impl_producer!(
+            $name,
+            |block: &[u8]| {
+                $crate::fastq::correct_block_size_with(
+                    block,
+                    &$crate::fastq::Sigils {
+                        header: $header,
+                        separator: $separator,
+                    },
+                )
+            },
+            |data: &[u8]| {
+                $crate::fastq::find_record_start_with(
+                    data,
+                    &$crate::fastq::Sigils {
+                        header: $header,
+                        separator: $separator,
+                    },
+                )
+            },
+            |data: &[u8]| {
+                $crate::fastq::truncated_record_with(
+                    data,
+                    &$crate::fastq::Sigils {
+                        header: $header,
+                        separator: $separator,
+                    },
+                )
+            }
+        );
+    };
+}
+
+/// Search the begin of the partial record at the end of block, a boundary is the end of a complete record followed by a header line
+pub fn correct_block_size_with(block: &[u8], sigils: &Sigils) -> error::Result<u64> {
+    // candidates are line start from end to begin of block
+    let starts = memchr::memrchr_iter(b'\n', block)
+        .map(|pos| pos + 1)
+        .chain(std::iter::once(0));
+
+    let mut error_offset = None;
+    for (nb_line, start) in starts.enumerate() {
+        if block.get(start) == Some(&sigils.header) {
+            if let Some(end) = record_end(block, start, sigils) {
+                // record could be follow by a blank line
+                if end == block.len() || block[end] == sigils.header || block[end] == b'\n' {
+                    return Ok(end as u64);
                 }
-            } else {
-                return Ok((end + 1) as u64);
+                error_offset.get_or_insert(end);
             }
         }
+
+        // a complete record and a partial record can't be spread over more than 8 lines
+        if nb_line >= 8 {
+            return Err(error::Error::NotAFastqFile {
+                offset: error_offset.unwrap_or(start) as u64,
+            });
+        }
+    }
+
+    Err(error::Error::NoNewLineInBlock { offset: 0 })
+}
+
+/// Search the begin of an incomplete record at the end of file data, None if last record is complete
+pub fn truncated_record_with(data: &[u8], sigils: &Sigils) -> Option<usize> {
+    let end = data.len() - data.iter().rev().take_while(|c| **c == b'\n').count();
+    if end == 0 {
+        return None;
     }
 
-    Err(error::Error::NotAFastqFile)
-});
+    // last record of a complete file is the four last lines
+    let start = memchr::memrchr_iter(b'\n', &data[..end])
+        .nth(3)
+        .map_or(0, |pos| pos + 1);
+    let mut lines = data[start..end].split(|c| *c == b'\n');
+
+    match (lines.next(), lines.next(), lines.next(), lines.next()) {
+        (Some(comment), Some(sequence), Some(plus), Some(quality))
+            if comment.first() == Some(&sigils.header)
+                && plus.first() == Some(&sigils.separator)
+                && match_quality(sequence, quality) =>
+        {
+            None
+        }
+        // incomplete record start after last complete record
+        _ => Some(correct_block_size_with(&data[..end], sigils).map_or(start, |pos| pos as usize)),
+    }
+}
+
+/// Search the begin of the first record that follow a new line in data, a record start by a line begin by '@' and its third line begin by '+'
+///
+/// Data could start in the middle of a record, position 0 is never return.
+pub fn find_record_start(data: &[u8]) -> Option<usize> {
+    find_record_start_with(data, &FASTQ)
+}
 
-impl_reader!(Reader, |block: &'a block::Block, offset: &mut usize| {
-    if *offset == block.len() {
-        Ok(None)
+/// Same as [find_record_start] with other sigils
+pub fn find_record_start_with(data: &[u8], sigils: &Sigils) -> Option<usize> {
+    let mut starts = memchr::memchr_iter(b'\n', data).map(|pos| pos + 1);
+    let mut comment = starts.next()?;
+    let mut sequence = starts.next()?;
+
+    for plus in starts {
+        if data.get(comment) == Some(&sigils.header) && data.get(plus) == Some(&sigils.separator) {
+            return Some(comment);
+        }
+
+        comment = sequence;
+        sequence = plus;
+    }
+
+    None
+}
+
+/// Position after the end of record that start at start, None if lines at start didn't form a complete fastq record
+///
+/// Record must have four lines, third begin by separator and sequence and quality must have same length.
+fn record_end(data: &[u8], start: usize, sigils: &Sigils) -> Option<usize> {
+    let sequence = start + memchr::memchr(b'\n', &data[start..])? + 1;
+    let plus = sequence + memchr::memchr(b'\n', &data[sequence..])? + 1;
+    if data.get(plus) != Some(&sigils.separator) {
+        return None;
+    }
+
+    let quality = plus + memchr::memchr(b'\n', &data[plus..])? + 1;
+    let end = quality + memchr::memchr(b'\n', &data[quality..])?;
+
+    if match_quality(&data[sequence..plus - 1], &data[quality..end]) {
+        Some(end + 1)
     } else {
-        let comment = &block.data()[Self::get_line(block, offset)?];
-        *offset += comment.len() + 1;
+        None
+    }
+}
+
+/// True if quality line could be quality of sequence line, same length or a `*` placeholder
+fn match_quality(sequence: &[u8], quality: &[u8]) -> bool {
+    sequence.len() == quality.len() || quality == b"*"
+}
+
+/// Replace `*` placeholder of sequence or quality by an empty slice, a `*` quality of a single base sequence is a real quality
+///
+/// If lines of block end by `\r\n`, `\r` is remove before placeholders are search.
+fn remove_placeholders<'a>(
+    block: &block::Block,
+    mut record: block::Record<'a>,
+) -> block::Record<'a> {
+    if block.is_crlf() {
+        record = record.trim_carriage_return();
+    }
+
+    if record.sequence == b"*" {
+        record.sequence = &record.sequence[..0];
+    }
+    if record.quality == b"*" && record.sequence.len() != 1 {
+        record.quality = &record.quality[..0];
+    }
+
+    record
+}
+
+impl_chunked_producer!(ChunkedProducer, Producer);
 
-        let sequence = &block.data()[Self::get_line(block, offset)?];
-        *offset += sequence.len() + 1;
+#[cfg(feature = "async-tokio")]
+impl_async_producer!(AsyncProducer, Producer);
 
-        let plus = &block.data()[Self::get_line(block, offset)?];
-        *offset += plus.len() + 1;
+impl_reader!(
+    Reader,
+    |block: &'a block::Block, offset: &mut usize| {
+        if *offset >= block.len() {
+            Ok(None)
+        } else {
+            let comment = &block.data()[Self::get_line(block, offset)?];
+            *offset += comment.len() + 1;
 
-        let quality = &block.data()[Self::get_line(block, offset)?];
-        *offset += quality.len() + 1;
+            let sequence = &block.data()[Self::get_line(block, offset)?];
+            *offset += sequence.len() + 1;
 
-        Ok(Some(block::Record {
-            comment,
-            sequence,
-            plus,
-            quality,
-        }))
+            let plus = &block.data()[Self::get_line(block, offset)?];
+            *offset += plus.len() + 1;
+
+            let quality = &block.data()[Self::get_line(block, offset)?];
+            *offset += quality.len() + 1;
+
+            Ok(Some(remove_placeholders(
+                block,
+                block::Record {
+                    comment,
+                    sequence,
+                    plus,
+                    quality,
+                },
+            )))
+        }
+    },
+    |record: &block::Record, offset: u64| {
+        if record.has_quality() && record.sequence.len() != record.quality.len() {
+            Err(error::Error::SeqQualLengthMismatch {
+                offset,
+                seq_len: record.sequence.len(),
+                qual_len: record.quality.len(),
+            })
+        } else {
+            Ok(())
+        }
+    },
+    |block: &'a block::Block, offset: &mut usize, length: usize| {
+        let data = block.data();
+        if data.get(*offset) != Some(&b'@') {
+            return None;
+        }
+
+        let comment_end = *offset + memchr::memchr(b'\n', &data[*offset..])?;
+        let sequence = comment_end + 1;
+
+        // sequence must end where expected and be followed by plus line
+        let plus = sequence + length + 1;
+        if data.get(plus - 1) != Some(&b'\n')
+            || data.get(plus) != Some(&b'+')
+            || memchr::memchr(b'\n', &data[sequence..plus - 1]).is_some()
+        {
+            return None;
+        }
+
+        // quality must end where expected and be followed by end of block or next record
+        let quality = plus + memchr::memchr(b'\n', &data[plus..])? + 1;
+        let end = quality + length;
+        if end > data.len()
+            || memchr::memchr(b'\n', &data[quality..end]).is_some()
+            || !matches!(data.get(end), None | Some(b'\n'))
+            || !matches!(data.get(end + 1), None | Some(b'@'))
+        {
+            return None;
+        }
+
+        let comment = &data[*offset..comment_end];
+        *offset = end + 1;
+
+        Some(remove_placeholders(
+            block,
+            block::Record {
+                comment,
+                sequence: &data[sequence..plus - 1],
+                plus: &data[plus..quality - 1],
+                quality: &data[quality..end],
+            },
+        ))
     }
-});
+);
+
+impl_lenient_reader!(LenientReader, Reader, find_record_start);
+
+/// Write records in fastq format
+pub struct Writer<W>
+where
+    W: std::io::Write,
+{
+    inner: W,
+}
+
+impl<W> Writer<W>
+where
+    W: std::io::Write,
+{
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Write an [OwnedRecord](block::OwnedRecord)
+    pub fn write_record(&mut self, record: &block::OwnedRecord) -> error::Result<()> {
+        self.write(&record.as_record())
+    }
+
+    /// Write a [Record](block::Record), sigil of comment is replace by '@', an empty plus line is write as '+'
+    ///
+    /// An empty sequence or a missing quality is write as a `*` placeholder, see [has_quality](block::Record::has_quality).
+    pub fn write(&mut self, record: &block::Record) -> error::Result<()> {
+        let comment = match record.comment.first() {
+            Some(b'@') | Some(b'>') => &record.comment[1..],
+            _ => record.comment,
+        };
+        let plus = if record.plus.is_empty() {
+            &b"+"[..]
+        } else {
+            record.plus
+        };
+        let sequence = if record.sequence.is_empty() {
+            &b"*"[..]
+        } else {
+            record.sequence
+        };
+        let quality = if record.has_quality() {
+            record.quality
+        } else {
+            &b"*"[..]
+        };
+
+        self.write_all(b"@")?;
+        self.write_all(comment)?;
+        self.write_all(b"\n")?;
+        self.write_all(sequence)?;
+        self.write_all(b"\n")?;
+        self.write_all(plus)?;
+        self.write_all(b"\n")?;
+        self.write_all(quality)?;
+        self.write_all(b"\n")
+    }
+
+    /// Get underlying writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> error::Result<()> {
+        self.inner
+            .write_all(data)
+            .map_err(|source| error::Error::WriteFile { source })
+    }
+}
+
+/// Number of complete fastq records in data
+pub(crate) fn count_records(data: &[u8]) -> usize {
+    let mut nb_line = memchr::memchr_iter(b'\n', data).count();
+
+    if data.last().map(|c| *c != b'\n').unwrap_or(false) {
+        nb_line += 1;
+    }
+
+    nb_line / 4
+}
+
+/// Position of the end of the nb_record-th record in data
+pub(crate) fn records_end(data: &[u8], nb_record: usize) -> usize {
+    if nb_record == 0 {
+        return 0;
+    }
+
+    memchr::memchr_iter(b'\n', data)
+        .nth(nb_record * 4 - 1)
+        .map(|pos| pos + 1)
+        .unwrap_or_else(|| data.len())
+}
+
+/// Number of blocks of [DEFAULT_BLOCKSIZE](crate::DEFAULT_BLOCKSIZE) read by [estimate_records]
+pub const ESTIMATE_SAMPLE_BLOCKS: usize = 4;
+
+/// Estimation of number of records in a fastq file, see [estimate_records]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordEstimate {
+    /// Estimated number of records
+    pub records: u64,
+    /// Number of records read to build estimation
+    pub sampled: u64,
+    /// Ratio between file length and number of bytes read, 1.0 if whole file is read
+    pub factor: f64,
+}
+
+impl RecordEstimate {
+    /// Sampled records divided by extrapolation factor, higher is better, estimation is exact if factor is 1.0
+    pub fn confidence(&self) -> f64 {
+        self.sampled as f64 / self.factor
+    }
+}
+
+/// Estimate number of records in fastq file from average length of records in first blocks and file length
+///
+/// Only [ESTIMATE_SAMPLE_BLOCKS] blocks are read, result is a capacity hint not an exact count.
+pub fn estimate_records<P>(path: P) -> error::Result<RecordEstimate>
+where
+    P: AsRef<std::path::Path>,
+{
+    let mut producer = Producer::with_blocksize(crate::DEFAULT_BLOCKSIZE, path)?;
+
+    let mut sampled = 0;
+    let mut bytes = 0;
+    for block in producer.by_ref().take(ESTIMATE_SAMPLE_BLOCKS) {
+        let block = block?;
+
+        sampled += count_records(block.data()) as u64;
+        bytes += block.len() as u64;
+    }
+
+    if bytes == 0 || producer.offset() >= producer.file_length() {
+        return Ok(RecordEstimate {
+            records: sampled,
+            sampled,
+            factor: 1.0,
+        });
+    }
+
+    let factor = producer.file_length() as f64 / bytes as f64;
+
+    Ok(RecordEstimate {
+        records: (sampled as f64 * factor).round() as u64,
+        sampled,
+        factor,
+    })
+}
+
+/// Infer Phred offset (33 or 64) of fastq file from min and max quality byte of sample_records first records
+pub fn detect_phred_offset<P>(path: P, sample_records: usize) -> error::Result<u8>
+where
+    P: AsRef<std::path::Path>,
+{
+    let mut min = u8::MAX;
+    let mut max = u8::MIN;
+    let mut nb_record = 0;
+
+    'blocks: for block in Producer::new(path)? {
+        let mut reader = Reader::new(block?);
+
+        while let Some(record) = reader.next_record()? {
+            if nb_record >= sample_records {
+                break 'blocks;
+            }
+
+            for q in record.quality {
+                min = min.min(*q);
+                max = max.max(*q);
+            }
+            nb_record += 1;
+        }
+    }
+
+    // Phred+64 start at ';' (59) for Solexa, Phred+33 rarely go upper than 'J' (74)
+    if min < 59 {
+        Ok(33)
+    } else if min >= 64 && max > 74 {
+        Ok(64)
+    } else {
+        Err(error::Error::AmbiguousPhred { min, max })
+    }
+}
+
+/// Deterministic decision to keep record, true for a fraction of records
+///
+/// Decision depend only on seed and id of record, without mate suffix ('/1' or '/2'), so mates of a pair get the same decision.
+pub fn keep(record: &block::Record, fraction: f64, seed: u64) -> bool {
+    let id = record.id();
+    let id = match id {
+        [prefix @ .., b'/', b'1'] | [prefix @ .., b'/', b'2'] => prefix,
+        _ => id,
+    };
+
+    // FNV-1a hash, stable across run and platform
+    let mut hash = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for c in id {
+        hash ^= *c as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    // finalizer of splitmix64 to spread bits
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+
+    (hash as f64) < fraction * u64::MAX as f64
+}
+
+/// Write records of fastq file selected by [keep] in output, return number of records write
+pub fn subsample<P, W>(path: P, fraction: f64, seed: u64, output: W) -> error::Result<u64>
+where
+    P: AsRef<std::path::Path>,
+    W: std::io::Write,
+{
+    let mut writer = Writer::new(output);
+    let mut nb_record = 0;
+
+    try_for_each(path, |record| {
+        if keep(&record, fraction, seed) {
+            nb_record += 1;
+            writer.write(&record)?;
+        }
+
+        Ok(())
+    })?;
+
+    writer
+        .into_inner()
+        .flush()
+        .map_err(|source| error::Error::WriteFile { source })?;
+
+    Ok(nb_record)
+}
+
+/// Write records of fastq file for which predicate return true in output, return number of records write
+///
+/// File is read block by block, memory usage doesn't depend on file size.
+pub fn filter<P, W, F>(input: P, output: W, mut predicate: F) -> error::Result<u64>
+where
+    P: AsRef<std::path::Path>,
+    W: std::io::Write,
+    F: FnMut(&block::Record) -> bool,
+{
+    let mut writer = Writer::new(output);
+    let mut nb_record = 0;
+
+    try_for_each(input, |record| {
+        if predicate(&record) {
+            nb_record += 1;
+            writer.write(&record)?;
+        }
+
+        Ok(())
+    })?;
+
+    writer
+        .into_inner()
+        .flush()
+        .map_err(|source| error::Error::WriteFile { source })?;
+
+    Ok(nb_record)
+}
+
+/// Write records of paired files r1 and r2 in output, mate 1 is followed by its mate 2
+///
+/// Files must contain the same number of records else an [UnpairedRecords](error::Error::UnpairedRecords) error is return.
+pub fn interleave<P, W>(r1: P, r2: P, output: W) -> error::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    W: std::io::Write,
+{
+    let mut writer = Writer::new(output);
+
+    for blocks in paired::Producer::new(r1, r2)? {
+        let mut reader = paired::Reader::new(blocks?);
+
+        while let Some((mate1, mate2)) = reader.next_record()? {
+            writer.write(&mate1)?;
+            writer.write(&mate2)?;
+        }
+    }
+
+    writer
+        .into_inner()
+        .flush()
+        .map_err(|source| error::Error::WriteFile { source })
+}
+
+/// Write records of interleaved file in r1_out and r2_out, first record of each pair go in r1_out and second in r2_out
+///
+/// File must contain an even number of records else an [UnpairedRecords](error::Error::UnpairedRecords) error is return.
+pub fn deinterleave<P, W>(input: P, r1_out: W, r2_out: W) -> error::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    W: std::io::Write,
+{
+    let mut writer1 = Writer::new(r1_out);
+    let mut writer2 = Writer::new(r2_out);
+
+    for block in interleaved::Producer::new(input)? {
+        let mut reader = interleaved::Reader::new(block?);
+
+        while let Some((mate1, mate2)) = reader.next_record()? {
+            writer1.write(&mate1)?;
+            writer2.write(&mate2)?;
+        }
+    }
+
+    writer1
+        .into_inner()
+        .flush()
+        .map_err(|source| error::Error::WriteFile { source })?;
+    writer2
+        .into_inner()
+        .flush()
+        .map_err(|source| error::Error::WriteFile { source })
+}
+
+/// Split fastq file in nb_chunk files, named chunk_{i}.fastq, in out_dir, each file end on a record boundary
+///
+/// Bytes are copied without parsing, boundaries are found as in [ChunkedProducer].
+pub fn split<P, O>(path: P, nb_chunk: u64, out_dir: O) -> error::Result<Vec<std::path::PathBuf>>
+where
+    P: AsRef<std::path::Path>,
+    O: AsRef<std::path::Path>,
+{
+    use std::io::Write;
+
+    let producer = Producer::new(path)?;
+
+    let mut paths = Vec::new();
+    let mut begin = 0;
+    for (i, end) in producer.chunk_ends(nb_chunk)?.into_iter().enumerate() {
+        let chunk_path = out_dir.as_ref().join(format!("chunk_{}.fastq", i));
+        let mut output = std::fs::File::create(&chunk_path)
+            .map_err(|source| error::Error::OpenFile { source })?;
+
+        output
+            .write_all(producer.block_range(begin, end)?.data())
+            .map_err(|source| error::Error::WriteFile { source })?;
+
+        paths.push(chunk_path);
+        begin = end;
+    }
+
+    Ok(paths)
+}
+
+/// Build a Block producer of fastq records read in reader, like stdin or a pipe, blocks could only be read sequentially
+pub fn from_reader<R>(reader: R) -> crate::stream::Producer
+where
+    R: std::io::Read + Send + 'static,
+{
+    crate::stream::Producer::with_format(
+        crate::fastx::Format::Fastq,
+        crate::DEFAULT_BLOCKSIZE,
+        reader,
+    )
+}
+
+/// Call f on each record of fastq file, records are read sequentially
+pub fn for_each<P, F>(path: P, mut f: F) -> error::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    F: FnMut(block::Record),
+{
+    try_for_each(path, |record| {
+        f(record);
+        Ok(())
+    })
+}
+
+/// Call f on each record of fastq file, records are read sequentially, stop at first error
+pub fn try_for_each<P, F>(path: P, mut f: F) -> error::Result<()>
+where
+    P: AsRef<std::path::Path>,
+    F: FnMut(block::Record) -> error::Result<()>,
+{
+    for block in Producer::new(path)? {
+        let mut reader = Reader::new(block?);
+
+        while let Some(record) = reader.next_record()? {
+            f(record)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Call f on each record of fastq file until f return Some, records are read sequentially, reading stop at first Some or first error
+pub fn find_map<P, R, F>(path: P, mut f: F) -> error::Result<Option<R>>
+where
+    P: AsRef<std::path::Path>,
+    F: FnMut(block::Record) -> Option<R>,
+{
+    for block in Producer::new(path)? {
+        let mut reader = Reader::new(block?);
+
+        while let Some(record) = reader.next_record()? {
+            if let Some(result) = f(record) {
+                return Ok(Some(result));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Call f on records of fastq file until f return Some, blocks are read in parallel
+///
+/// Result could come from any record for which f return Some, not necessarily the first one in file, parsing stop soon after a Some or an error.
+pub fn par_find_map<P, R, F>(path: P, f: F) -> error::Result<Option<R>>
+where
+    P: AsRef<std::path::Path>,
+    R: Send,
+    F: Fn(block::Record) -> Option<R> + Sync + Send,
+{
+    use rayon::iter::ParallelBridge;
+    use rayon::iter::ParallelIterator;
+
+    Producer::new(path)?
+        .par_bridge()
+        .map(|block| {
+            let mut reader = Reader::new(block?);
+
+            while let Some(record) = reader.next_record()? {
+                if let Some(result) = f(record) {
+                    return Ok(Some(result));
+                }
+            }
+
+            Ok(None)
+        })
+        .find_map_any(|result| match result {
+            Ok(None) => None,
+            found => Some(found),
+        })
+        .unwrap_or(Ok(None))
+}
+
+/// Parse fastq file in parallel, each thread fold records in is own accumulator build by init, accumulators are merged by merge
+pub fn parse_fold<P, A, I, F, M>(path: P, init: I, fold: F, merge: M) -> error::Result<A>
+where
+    P: AsRef<std::path::Path>,
+    A: Send,
+    I: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, block::Record) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    crate::parser::fold::fold(Producer::new(path)?, Reader::read_record, init, fold, merge)
+}
+
+/// Same as [parse_fold] but fold get all records of a block at once, see [fold_batch](crate::parser::fold::fold_batch)
+pub fn parse_fold_batch<P, A, I, F, M>(path: P, init: I, fold: F, merge: M) -> error::Result<A>
+where
+    P: AsRef<std::path::Path>,
+    A: Send,
+    I: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, &[block::Record]) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    crate::parser::fold::fold_batch(Producer::new(path)?, Reader::read_record, init, fold, merge)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::io::Write;
+
     mod producer {
         use super::*;
 
@@ -109,19 +795,524 @@ TTAGATTATAGTACGGTATAGTGGTTACTATGTAGCCTAAGTGGCGCCCGTTGTAGAGGAATCCACTTATATAACACAGG
             );
         }
 
+        #[test]
+        fn length_from_open_file() {
+            let mut file = crate::tests::generate_fastq(42, 10, 150);
+
+            let mut producer = Producer::new(file.path()).unwrap();
+            assert_eq!(producer.file_length(), 3080);
+
+            file.as_file_mut()
+                .write_all(b"@10\nACGT\n+10\n!!!!\n")
+                .unwrap();
+
+            assert_eq!(producer.file_length(), 3080);
+            assert_eq!(producer.next_block().unwrap().unwrap().len(), 3080);
+            assert!(producer.next_block().unwrap().is_none());
+        }
+
+        #[test]
+        fn from_vec() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+            let data = std::fs::read(file.path()).unwrap();
+
+            let mut tmp = Producer::from_vec(crate::DEFAULT_BLOCKSIZE, data);
+            assert!(tmp.file().is_none());
+
+            let mut block_length = Vec::new();
+            while let Ok(Some(block)) = tmp.next_block() {
+                block_length.push(block.len());
+            }
+
+            assert_eq!(block_length, vec![65300, 65520, 65520, 65520, 49920]);
+        }
+
+        #[test]
+        fn from_file() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+            let expected = Producer::with_blocksize(1_000, file.path())
+                .unwrap()
+                .next_block()
+                .unwrap()
+                .unwrap()
+                .len();
+
+            let mut producer =
+                Producer::from_file(1_000, std::fs::File::open(file.path()).unwrap()).unwrap();
+            assert_eq!(producer.file_length(), 311_780);
+            assert_eq!(producer.next_block().unwrap().unwrap().len(), expected);
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::io::IntoRawFd;
+
+                let fd = std::fs::File::open(file.path()).unwrap().into_raw_fd();
+                let mut producer = unsafe { Producer::from_raw_fd(1_000, fd) }.unwrap();
+                assert_eq!(producer.next_block().unwrap().unwrap().len(), expected);
+            }
+        }
+
+        #[test]
+        fn builder() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+            let producer = Producer::builder().build(file.path()).unwrap();
+            assert_eq!(
+                producer.blocksize(),
+                Producer::new(file.path()).unwrap().blocksize()
+            );
+
+            let steps = std::sync::Arc::new(std::sync::Mutex::new(0));
+            let steps_cb = steps.clone();
+            let mut producer = Producer::builder()
+                .blocksize(1_000)
+                .advice(memmap2::Advice::WillNeed)
+                .on_progress(move |_, _| *steps_cb.lock().unwrap() += 1)
+                .build(file.path())
+                .unwrap();
+            assert_eq!(producer.blocksize(), 1_000);
+
+            while producer.next_block().unwrap().is_some() {}
+            assert!(*steps.lock().unwrap() > 300);
+
+            let mut producer = Producer::builder()
+                .populate(true)
+                .advice(memmap2::Advice::Random)
+                .build(file.path())
+                .unwrap();
+            let mut nb_record = 0;
+            while let Some(block) = producer.next_block().unwrap() {
+                nb_record += Reader::new(block).records().count();
+            }
+            assert_eq!(nb_record, 1_000);
+
+            let mut truncated = tempfile::NamedTempFile::new().unwrap();
+            truncated.write_all(b"@1\nA\n+\n!\n@2\nT\n+").unwrap();
+
+            let mut producer = Producer::builder()
+                .lenient(true)
+                .build(truncated.path())
+                .unwrap();
+            let mut reader = Reader::new(producer.next_block().unwrap().unwrap());
+            assert!(reader.next_record().unwrap().is_some());
+            assert!(matches!(
+                reader.next_record(),
+                Err(error::Error::PartialRecord { offset: 15 })
+            ));
+        }
+
+        #[test]
+        fn follow() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(b"@1\nACGT\n+\n!!!!\n@2\nTG").unwrap();
+
+            let finished = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+            let mut producer = Producer::new(file.path()).unwrap().follow(
+                1_000,
+                block::Follow::new(finished.clone()).interval(std::time::Duration::from_millis(5)),
+            );
+
+            let mut writer = file.reopen().unwrap();
+            let appender = std::thread::spawn(move || {
+                use std::io::Seek;
+
+                writer.seek(std::io::SeekFrom::End(0)).unwrap();
+                for chunk in [&b"CA\n+\n"[..], b"####", b"\n@3\nAA\n+\n", b"$$\n"] {
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    writer.write_all(chunk).unwrap();
+                }
+                finished.store(true, std::sync::atomic::Ordering::Release);
+            });
+
+            let mut comments = Vec::new();
+            let mut nb_block = 0;
+            while let Some(block) = producer.next_block().unwrap() {
+                nb_block += 1;
+                Reader::new_validated(block)
+                    .for_each(|record| comments.push(record.comment.to_vec()))
+                    .unwrap();
+            }
+            appender.join().unwrap();
+
+            assert!(nb_block > 1);
+            assert_eq!(
+                comments,
+                vec![b"@1".to_vec(), b"@2".to_vec(), b"@3".to_vec()]
+            );
+        }
+
+        #[test]
+        fn prefetch_evict() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+            let mut producer = Producer::with_blocksize(1_000, file.path()).unwrap();
+            producer.prefetch().unwrap();
+
+            let mut nb_record = 0;
+            while let Some(block) = producer.next_block().unwrap() {
+                Reader::new(block).for_each(|_| nb_record += 1).unwrap();
+                producer.evict().unwrap();
+            }
+            assert_eq!(nb_record, 1_000);
+
+            let producer = Producer::from_vec(1_000, b"@1\nA\n+\n!\n".to_vec());
+            producer.prefetch().unwrap();
+            producer.evict().unwrap();
+        }
+
+        #[test]
+        fn evict_consumed() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+            let mut expected = Vec::new();
+            crate::fastq::for_each(file.path(), |record| expected.push(record.to_owned())).unwrap();
+
+            let mut producer = Producer::builder()
+                .blocksize(1_000)
+                .evict_consumed(true)
+                .build(file.path())
+                .unwrap();
+
+            let mut records = Vec::new();
+            while let Some(block) = producer.next_block().unwrap() {
+                let copy = block.clone();
+                let data = block.data().to_vec();
+
+                // pages of clone are drop, block read them again
+                drop(copy);
+                assert_eq!(block.data(), &data[..]);
+
+                Reader::new(block)
+                    .for_each(|record| records.push(record.to_owned()))
+                    .unwrap();
+            }
+            assert_eq!(records, expected);
+
+            // section already read and evicted is map again
+            let block = producer.block_range(0, 1_000).unwrap();
+            assert_eq!(block.data(), &std::fs::read(file.path()).unwrap()[..1_000]);
+        }
+
+        #[test]
+        fn line_ending() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(
+                b"@1\r\nACGT\r\n+\r\n!!!!\r\n@2\r\n*\r\n+\r\n*\r\n@3\r\nTGCA\r\n+\r\n####\r\n",
+            )
+            .unwrap();
+
+            for line_ending in [block::LineEnding::Crlf, block::LineEnding::Auto] {
+                let mut producer = Producer::builder()
+                    .line_ending(line_ending)
+                    .build(file.path())
+                    .unwrap();
+                let mut reader = Reader::new_validated(producer.next_block().unwrap().unwrap());
+
+                let mut records = Vec::new();
+                while let Some(record) = reader.next_record().unwrap() {
+                    records.push((
+                        record.comment.to_vec(),
+                        record.sequence.to_vec(),
+                        record.plus.to_vec(),
+                        record.quality.to_vec(),
+                    ));
+                }
+                assert_eq!(
+                    records,
+                    vec![
+                        (
+                            b"@1".to_vec(),
+                            b"ACGT".to_vec(),
+                            b"+".to_vec(),
+                            b"!!!!".to_vec()
+                        ),
+                        (b"@2".to_vec(), b"".to_vec(), b"+".to_vec(), b"".to_vec()),
+                        (
+                            b"@3".to_vec(),
+                            b"TGCA".to_vec(),
+                            b"+".to_vec(),
+                            b"####".to_vec()
+                        ),
+                    ]
+                );
+            }
+
+            // uniform length fast path
+            let mut uniform = tempfile::NamedTempFile::new().unwrap();
+            for i in 0..100 {
+                write!(uniform, "@{}\r\nACGT\r\n+\r\n!!!!\r\n", i).unwrap();
+            }
+            let mut producer = Producer::builder()
+                .line_ending(block::LineEnding::Crlf)
+                .build(uniform.path())
+                .unwrap();
+            let mut reader = Reader::new(producer.next_block().unwrap().unwrap());
+            let mut nb_record = 0;
+            while let Some(record) = reader.next_record().unwrap() {
+                assert_eq!(
+                    (record.sequence, record.quality),
+                    (&b"ACGT"[..], &b"!!!!"[..])
+                );
+                nb_record += 1;
+            }
+            assert_eq!(nb_record, 100);
+            assert_eq!(reader.uniform_length(), Some(4));
+
+            let mut producer = Producer::builder().build(file.path()).unwrap();
+            let block = producer.next_block().unwrap().unwrap();
+            assert!(!block.is_crlf());
+            let mut reader = Reader::new(block);
+            assert_eq!(reader.next_record().unwrap().unwrap().sequence, b"ACGT\r");
+
+            let mut lf = tempfile::NamedTempFile::new().unwrap();
+            lf.write_all(b"@1\nACGT\n+\n!!!!\n").unwrap();
+            let mut producer = Producer::builder()
+                .line_ending(block::LineEnding::Auto)
+                .build(lf.path())
+                .unwrap();
+            assert!(!producer.next_block().unwrap().unwrap().is_crlf());
+        }
+
+        #[test]
+        fn progress() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+            let steps = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let steps_cb = steps.clone();
+
+            let mut tmp = Producer::with_blocksize(crate::DEFAULT_BLOCKSIZE, file.path())
+                .unwrap()
+                .on_progress(move |done, total| steps_cb.lock().unwrap().push((done, total)));
+
+            while let Ok(Some(_)) = tmp.next_block() {}
+
+            assert_eq!(
+                *steps.lock().unwrap(),
+                vec![
+                    (65300, 311780),
+                    (130820, 311780),
+                    (196340, 311780),
+                    (261860, 311780),
+                    (311780, 311780)
+                ]
+            );
+        }
+
+        #[test]
+        fn seek_to() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+            let mut producer = Producer::new(file.path()).unwrap();
+
+            producer.seek_to(5).unwrap();
+            assert_eq!(producer.position(), 308);
+
+            let block = producer.next_block().unwrap().unwrap();
+            let checkpoint = producer.position();
+
+            let mut nb_record = 1 + count_records(block.data());
+
+            let mut resume = Producer::new(file.path()).unwrap();
+            resume.seek_to(checkpoint).unwrap();
+            assert_eq!(resume.position(), checkpoint);
+
+            while let Some(block) = resume.next_block().unwrap() {
+                let mut reader = Reader::new(block);
+                while reader.next_record().unwrap().is_some() {
+                    nb_record += 1;
+                }
+            }
+            assert_eq!(nb_record, 1_000);
+        }
+
+        #[test]
+        fn find_record_start() {
+            assert_eq!(
+                Producer::find_record_start(b"@A\n@1\nACGT\n+\n@ABC\n"),
+                Some(3)
+            );
+            assert_eq!(
+                Producer::find_record_start(b"T\n+\n@ABC\n@1\nACGT\n+\n@ABC\n"),
+                Some(9)
+            );
+            assert_eq!(Producer::find_record_start(b"T\n@1\nAC"), None);
+            assert_eq!(
+                crate::fastq::find_record_start(b"!!\n@1\nA\n+\n!\n"),
+                Some(3)
+            );
+        }
+
+        #[test]
+        fn chunked() {
+            let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+            let producer = ChunkedProducer::with_threads(7, file.path()).unwrap();
+            let blocks = producer
+                .collect::<error::Result<Vec<block::Block>>>()
+                .unwrap();
+
+            assert_eq!(blocks.len(), 7);
+            assert_eq!(blocks[0].file_offset(), 0);
+            for pair in blocks.windows(2) {
+                assert_eq!(
+                    pair[0].file_offset() + pair[0].len() as u64,
+                    pair[1].file_offset()
+                );
+                assert_eq!(pair[1].data()[0], b'@');
+            }
+
+            let mut nb_record = 0;
+            for block in blocks {
+                let mut reader = Reader::new(block);
+                while reader.next_record().unwrap().is_some() {
+                    nb_record += 1;
+                }
+            }
+            assert_eq!(nb_record, 1_000);
+        }
+
+        #[test]
+        fn default_blocksize() {
+            let producer = Producer::new(crate::tests::generate_fastq(42, 2, 150)).unwrap();
+            assert_eq!(producer.blocksize(), 616);
+
+            let producer = Producer::new(crate::tests::generate_fastq(42, 1_000, 150)).unwrap();
+            assert_eq!(producer.blocksize(), crate::default_blocksize(311_780));
+        }
+
+        #[test]
+        fn blocksize_too_small() {
+            let mut producer =
+                Producer::with_blocksize(100, crate::tests::generate_fastq(42, 1_000, 150))
+                    .unwrap();
+
+            assert!(matches!(
+                producer.next_block(),
+                Err(error::Error::BlocksizeTooSmall {
+                    blocksize: 100,
+                    min_needed: 400
+                })
+            ));
+
+            let mut producer =
+                Producer::with_blocksize(400, crate::tests::generate_fastq(42, 1_000, 150))
+                    .unwrap();
+            assert!(producer.next_block().unwrap().is_some());
+        }
+
+        #[test]
+        fn quality_full_of_sigil() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            for i in 0..200 {
+                let length = 1 + (i * 7) % 23;
+                let sequence = (0..length).map(|j| b"ACGT"[j % 4]).collect::<Vec<u8>>();
+                let quality = (0..length)
+                    .map(|j| if (i + j) % 3 == 0 { b'+' } else { b'@' })
+                    .collect::<Vec<u8>>();
+
+                file.write_all(format!("@r{}\n", i).as_bytes()).unwrap();
+                file.write_all(&sequence).unwrap();
+                file.write_all(b"\n+\n").unwrap();
+                file.write_all(&quality).unwrap();
+                file.write_all(b"\n").unwrap();
+            }
+
+            for blocksize in [80, 100, 150, 333, 1_000, 10_000] {
+                let mut producer = Producer::with_blocksize(blocksize, file.path()).unwrap();
+
+                let mut ids = Vec::new();
+                while let Some(block) = producer.next_block().unwrap() {
+                    Reader::new_validated(block)
+                        .for_each(|record| ids.push(record.id().to_vec()))
+                        .unwrap();
+                }
+
+                assert_eq!(
+                    ids,
+                    (0..200)
+                        .map(|i| format!("r{}", i).into_bytes())
+                        .collect::<Vec<Vec<u8>>>()
+                );
+            }
+        }
+
+        #[test]
+        fn correct_block_size_ambiguous() {
+            // quality begin by '@' and '+'
+            assert_eq!(
+                Producer::correct_block_size(b"@1\nAC\n+\n@+\n@2\nAC\n+\n+@\n@3\nA").unwrap(),
+                22
+            );
+            assert_eq!(
+                Producer::correct_block_size(b"@1\nAC\n+\n@+\n@2\nAC\n+\n+@\n").unwrap(),
+                22
+            );
+            // last line is a quality that look like a header
+            assert_eq!(
+                Producer::correct_block_size(b"@1\nAC\n+\n@+\n@2\nACG\n+\n@@").unwrap(),
+                11
+            );
+        }
+
         #[test]
         fn with_blocksize_buffer_larger_file() {
             let mut tmp =
                 Producer::with_blocksize(8092, crate::tests::generate_fastq(44, 2, 150)).unwrap();
 
-            let block = tmp.next_block().unwrap().unwrap();
+            let block = tmp.next_block().unwrap().unwrap();
+
+            assert_eq!(block.len(), 616);
+        }
+
+        #[test]
+        fn blocksize_around_file_length() {
+            let file = crate::tests::generate_fastq(42, 10, 150);
+            let length = std::fs::metadata(file.path()).unwrap().len();
+
+            for blocksize in [length - 1, length, length + 1] {
+                let mut producer = Producer::with_blocksize(blocksize, file.path()).unwrap();
+
+                let mut nb_record = 0;
+                while let Some(block) = producer.next_block().unwrap() {
+                    let mut reader = Reader::new_validated(block);
+                    while reader.next_record().unwrap().is_some() {
+                        nb_record += 1;
+                    }
+                }
+
+                assert_eq!(nb_record, 10);
+            }
+        }
+
+        #[test]
+        fn blocksize_on_record_boundary() {
+            let file = crate::tests::generate_fastq(42, 10, 150);
+            let data = std::fs::read(file.path()).unwrap();
+
+            // records have same length, blocksize is a multiple of record length
+            let record_length = (data.len() / 10) as u64;
+            for blocksize in [record_length, record_length * 2, record_length * 5] {
+                let mut producer = Producer::with_blocksize(blocksize, file.path()).unwrap();
 
-            assert_eq!(block.len(), 616);
+                let mut nb_record = 0;
+                while let Some(block) = producer.next_block().unwrap() {
+                    let mut reader = Reader::new_validated(block);
+                    while reader.next_record().unwrap().is_some() {
+                        nb_record += 1;
+                    }
+                }
+
+                assert_eq!(nb_record, 10);
+                assert_eq!(producer.offset(), data.len() as u64);
+            }
         }
 
         #[test]
         fn get_all_block() {
-            let mut tmp = Producer::new(crate::tests::generate_fastq(42, 1_000, 150)).unwrap();
+            let mut tmp = Producer::with_blocksize(
+                crate::DEFAULT_BLOCKSIZE,
+                crate::tests::generate_fastq(42, 1_000, 150),
+            )
+            .unwrap();
 
             let mut block_length = Vec::new();
             while let Ok(Some(block)) = tmp.next_block() {
@@ -231,9 +1422,918 @@ GTCCCTCAATCCG
         }
     }
 
+    #[test]
+    fn error_offset() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(
+            b"@0\nACGT\n+\n!!!!\n@1\nACGT\n+\n!!!!\n@2\nACGT\n+\n!!!!\nxxxx\nyyyy\nzzzz\nwwww\nvvvv\nuuuu\n",
+        )
+        .unwrap();
+
+        let mut producer = Producer::with_blocksize(40, file.path()).unwrap();
+        assert_eq!(producer.next_block().unwrap().unwrap().file_offset(), 0);
+
+        match producer.next_block() {
+            Err(e @ error::Error::NotAFastqFile { .. }) => assert!(e.offset().unwrap() > 30),
+            _ => panic!("expect a NotAFastqFile error"),
+        }
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"@1\nA\n+\n!\n@2\nT\n+").unwrap();
+
+        let mut producer = Producer::new(file.path()).unwrap();
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::TruncatedFile { offset: 9 })
+        ));
+
+        let mut reader = Reader::new(block::Block::from_slice(b"@1\nA\n+\n!\n@2\nT\n+"));
+
+        assert!(reader.next_record().unwrap().is_some());
+        assert!(matches!(
+            reader.next_record(),
+            Err(error::Error::PartialRecord { offset: 15 })
+        ));
+    }
+
+    #[test]
+    fn error_context() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(
+            b"@0\nACGT\n+\n!!!!\n@1\nACGT\n+\n!!!!\n@2\nACGT\n+\n!!!!\nxxxx\nyyyy\nzzzz\nwwww\nvvvv\nuuuu\n",
+        )
+        .unwrap();
+
+        let mut producer = Producer::builder()
+            .blocksize(40)
+            .capture_context(true)
+            .build(file.path())
+            .unwrap();
+        producer.next_block().unwrap();
+
+        let error = producer.next_block().unwrap_err();
+        let context = error.context().unwrap();
+        assert!(context.after);
+        assert_eq!(
+            context.bytes.len(),
+            context.position + error::CONTEXT_LENGTH / 2
+        );
+        assert!(error.offset().unwrap() > 30);
+        assert!(error
+            .to_string()
+            .starts_with("Input file seems not be a fastq file, error at offset"));
+        assert!(error.to_string().contains(", found: "));
+
+        let mut reader = Reader::new(block::Block::from_vec(
+            100,
+            b"@1\nA\n+\n!\n@2\nT\n+".to_vec(),
+        ));
+        reader.set_capture_context(true);
+        assert!(reader.next_record().unwrap().is_some());
+
+        let error = match reader.next_record() {
+            Err(e) => e,
+            _ => panic!("expect a PartialRecord error"),
+        };
+        assert_eq!(error.offset(), Some(115));
+        assert_eq!(
+            error.to_string(),
+            "in_place_fastx found a partial record at offset 115, found: @1\\nA\\n+\\n!\\n@2\\nT\\n+"
+        );
+
+        // without capture, error is unchanged
+        let mut reader = Reader::new(block::Block::from_slice(b"@1\nA\n+\n!\n@2\nT\n+"));
+        reader.next_record().unwrap();
+        assert!(matches!(
+            reader.next_record(),
+            Err(error::Error::PartialRecord { offset: 15 })
+        ));
+    }
+
+    #[test]
+    fn empty_file() {
+        use std::io::Write;
+
+        for content in [&b""[..], b"\n", b"\r\n\n"] {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(content).unwrap();
+
+            let mut producer = Producer::new(file.path()).unwrap();
+            assert!(producer.next_block().unwrap().is_none());
+            assert!(producer.next_block().unwrap().is_none());
+        }
+
+        let mut producer = Producer::from_vec(1, Vec::new());
+        assert_eq!(producer.blocksize(), 0);
+        assert!(producer.next_block().unwrap().is_none());
+
+        let mut producer = Producer::from_vec(1_000, b"@1\nACGT\n+\n!!!!\n\n\n".to_vec());
+        assert!(producer.next_block().unwrap().is_some());
+        assert!(producer.next_block().unwrap().is_none());
+    }
+
+    #[test]
+    fn truncated_file() {
+        let data = std::fs::read(crate::tests::generate_fastq(42, 10, 150).path()).unwrap();
+        let record_len = data.len() / 10;
+
+        for cut in [1, 20, 152, 153, 155, 200, 304, record_len - 2] {
+            let length = record_len * 9 + cut;
+            let mut producer = Producer::from_vec(1_000, data[..length].to_vec());
+
+            let error = loop {
+                match producer.next_block() {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => panic!("truncation at {} isn't detected", length),
+                    Err(e) => break e,
+                }
+            };
+            assert!(matches!(
+                error,
+                error::Error::TruncatedFile { offset } if offset == (record_len * 9) as u64
+            ));
+        }
+
+        // last new line is missing, file isn't truncated
+        let mut producer = Producer::from_vec(1_000, data[..data.len() - 1].to_vec());
+        let mut nb_block = 0;
+        while producer.next_block().unwrap().is_some() {
+            nb_block += 1;
+        }
+        assert!(nb_block > 1);
+
+        assert_eq!(Producer::truncated_record(b"@1\nA\n+\n!\n\n\n"), None);
+        assert_eq!(Producer::truncated_record(b""), None);
+    }
+
+    #[test]
+    fn validated_reader() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"@0\nACGT\n+\n!!!!\n@1\nACGT\n+\n!!!\n@2\nACGT\n+\n!!!!\n")
+            .unwrap();
+
+        let mut producer = Producer::new(file.path()).unwrap();
+        let mut reader = Reader::new(producer.next_block().unwrap().unwrap());
+        let mut nb_record = 0;
+        while reader.next_record().unwrap().is_some() {
+            nb_record += 1;
+        }
+        assert_eq!(nb_record, 3);
+
+        let mut producer = Producer::new(file.path()).unwrap();
+        let mut reader = Reader::new_validated(producer.next_block().unwrap().unwrap());
+        assert!(reader.next_record().unwrap().is_some());
+        assert!(matches!(
+            reader.next_record(),
+            Err(error::Error::SeqQualLengthMismatch {
+                offset: 15,
+                seq_len: 4,
+                qual_len: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let data = std::fs::read(crate::tests::generate_fastq(42, 1_000, 150).path()).unwrap();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&block::UTF8_BOM).unwrap();
+        file.write_all(&data).unwrap();
+
+        for blocksize in [8192, u64::MAX] {
+            let mut producer = Producer::with_blocksize(blocksize, file.path()).unwrap();
+            assert_eq!(producer.offset(), 3);
+
+            let mut comments = Vec::new();
+            while let Some(block) = producer.next_block().unwrap() {
+                let mut reader = Reader::new_validated(block);
+                while let Some(record) = reader.next_record().unwrap() {
+                    comments.push(record.comment.to_vec());
+                }
+            }
+
+            assert_eq!(comments.len(), 1_000);
+            assert_eq!(comments[0], b"@0");
+        }
+
+        let mut bom = block::UTF8_BOM.to_vec();
+        bom.extend_from_slice(b"@1\nA\n+\n!\n");
+        let mut producer = Producer::from_vec(1_000, bom);
+        let block = producer.next_block().unwrap().unwrap();
+        assert_eq!(block.data(), b"@1\nA\n+\n!\n");
+        assert_eq!(block.file_offset(), 3);
+    }
+
+    #[test]
+    fn custom_sigils() {
+        crate::fastq_like_producer!(BangProducer, b'!', b'=');
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..100 {
+            writeln!(file, "!{}\nACGT\n=\n@@+@", i).unwrap();
+        }
+
+        let mut producer = BangProducer::with_blocksize(64, file.path()).unwrap();
+        let mut names = Vec::new();
+        while let Some(block) = producer.next_block().unwrap() {
+            let mut reader = Reader::new_validated(block);
+            while let Some(record) = reader.next_record().unwrap() {
+                names.push(record.name().to_vec());
+            }
+        }
+        assert_eq!(names.len(), 100);
+        assert_eq!(names[99], b"99");
+
+        assert_eq!(
+            find_record_start_with(
+                b"CGT\n=\n@@+@\n!1\nACGT\n=\n",
+                &Sigils {
+                    header: b'!',
+                    separator: b'='
+                }
+            ),
+            Some(11)
+        );
+
+        file.write_all(b"!100\nACGT\n=").unwrap();
+        assert!(matches!(
+            BangProducer::with_blocksize(64, file.path())
+                .unwrap()
+                .find_map(|block| block.err()),
+            Some(error::Error::TruncatedFile { .. })
+        ));
+        assert!(Producer::with_blocksize(64, file.path())
+            .unwrap()
+            .next_block()
+            .is_err());
+    }
+
+    #[cfg(feature = "async-tokio")]
+    #[test]
+    fn async_producer() {
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let nb_record = runtime.block_on(async {
+            let mut producer = AsyncProducer::with_blocksize(8192, file.path())
+                .await
+                .unwrap();
+
+            let mut nb_record = 0;
+            while let Some(block) = producer.next_block().await.unwrap() {
+                nb_record +=
+                    tokio::task::spawn_blocking(move || Reader::new(block).records().count())
+                        .await
+                        .unwrap();
+            }
+
+            nb_record
+        });
+        assert_eq!(nb_record, 1_000);
+
+        let mut truncated = tempfile::NamedTempFile::new().unwrap();
+        truncated.write_all(b"@1\nACGT\n+\n!!!!\n@2\nAC").unwrap();
+
+        runtime.block_on(async {
+            let mut producer = AsyncProducer::new(truncated.path()).await.unwrap();
+
+            assert!(matches!(
+                producer.next_block().await,
+                Err(error::Error::TruncatedFile { .. })
+            ));
+            assert!(producer.next_block().await.unwrap().is_none());
+
+            assert!(AsyncProducer::new("missing.fastq").await.is_err());
+        });
+    }
+
+    fn placeholder_fastq() -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..100 {
+            if i % 10 == 3 {
+                writeln!(file, "@{}\nACGT\n+\n*", i).unwrap();
+            } else if i == 42 {
+                writeln!(file, "@{}\n*\n+\n*", i).unwrap();
+            } else {
+                writeln!(file, "@{}\nACGT\n+\n!!!!", i).unwrap();
+            }
+        }
+        writeln!(file, "@single\nA\n+\n*").unwrap();
+
+        file
+    }
+
+    // name, sequence, has_quality and quality of a record
+    type PlaceholderRecord = (Vec<u8>, Vec<u8>, bool, Vec<u8>);
+
+    fn read_placeholder(path: &std::path::Path) -> Vec<PlaceholderRecord> {
+        let mut records = Vec::new();
+        let mut producer = Producer::with_blocksize(64, path).unwrap();
+        while let Some(block) = producer.next_block().unwrap() {
+            let mut reader = Reader::new_validated(block);
+            while let Some(record) = reader.next_record().unwrap() {
+                records.push((
+                    record.name().to_vec(),
+                    record.sequence.to_vec(),
+                    record.has_quality(),
+                    record.quality.to_vec(),
+                ));
+            }
+        }
+
+        records
+    }
+
+    #[test]
+    fn placeholder() {
+        let file = placeholder_fastq();
+        let records = read_placeholder(file.path());
+
+        assert_eq!(records.len(), 101);
+        assert_eq!(
+            records[0],
+            (b"0".to_vec(), b"ACGT".to_vec(), true, b"!!!!".to_vec())
+        );
+        assert_eq!(
+            records[13],
+            (b"13".to_vec(), b"ACGT".to_vec(), false, b"".to_vec())
+        );
+        assert_eq!(
+            records[42],
+            (b"42".to_vec(), b"".to_vec(), false, b"".to_vec())
+        );
+        assert_eq!(
+            records[100],
+            (b"single".to_vec(), b"A".to_vec(), true, b"*".to_vec())
+        );
+        assert_eq!(records.iter().filter(|record| !record.2).count(), 11);
+    }
+
+    #[test]
+    fn placeholder_round_trip() {
+        let file = placeholder_fastq();
+        let output = tempfile::NamedTempFile::new().unwrap();
+
+        let mut writer = Writer::new(output.reopen().unwrap());
+        crate::fastq::try_for_each(file.path(), |record| writer.write(&record)).unwrap();
+        drop(writer);
+
+        assert_eq!(
+            std::fs::read(output.path()).unwrap(),
+            std::fs::read(file.path()).unwrap()
+        );
+        assert_eq!(
+            read_placeholder(output.path()),
+            read_placeholder(file.path())
+        );
+    }
+
+    #[test]
+    fn count() {
+        assert_eq!(count_records(b""), 0);
+        assert_eq!(count_records(b"@1\nA\n+\n!\n@2\nT\n+\n!\n"), 2);
+        assert_eq!(count_records(b"@1\nA\n+\n!\n@2\nT\n+\n!"), 2);
+
+        assert_eq!(records_end(b"@1\nA\n+\n!\n@2\nT\n+\n!\n", 0), 0);
+        assert_eq!(records_end(b"@1\nA\n+\n!\n@2\nT\n+\n!\n", 1), 9);
+        assert_eq!(records_end(b"@1\nA\n+\n!\n@2\nT\n+\n!", 2), 17);
+    }
+
+    #[test]
+    fn writer() {
+        let mut writer = Writer::new(Vec::new());
+
+        writer
+            .write_record(&block::OwnedRecord {
+                comment: b"@1 desc".to_vec(),
+                sequence: b"ACGT".to_vec(),
+                plus: b"+1".to_vec(),
+                quality: b"!!!!".to_vec(),
+            })
+            .unwrap();
+        writer
+            .write(&block::Record {
+                comment: b">2",
+                sequence: b"TG",
+                plus: b"",
+                quality: b"##",
+            })
+            .unwrap();
+
+        let data = writer.into_inner();
+        assert_eq!(data, b"@1 desc\nACGT\n+1\n!!!!\n@2\nTG\n+\n##\n".to_vec());
+
+        let mut reader = Reader::new(block::Block::from_slice(&data));
+        reader.for_each(|_| ()).unwrap();
+    }
+
+    #[test]
+    fn estimate_records() {
+        let estimate =
+            super::estimate_records(crate::tests::generate_fastq(42, 10_000, 150)).unwrap();
+        assert!(estimate.sampled < 10_000);
+        assert!(estimate.factor > 1.0);
+        assert!((estimate.records as f64 - 10_000.0).abs() < 200.0);
+        assert!((estimate.confidence() - estimate.sampled as f64 / estimate.factor).abs() < 1e-9);
+
+        let estimate = super::estimate_records(crate::tests::generate_fastq(42, 100, 150)).unwrap();
+        assert_eq!(
+            estimate,
+            RecordEstimate {
+                records: 100,
+                sampled: 100,
+                factor: 1.0
+            }
+        );
+
+        let empty = tempfile::NamedTempFile::new().unwrap();
+        assert_eq!(super::estimate_records(empty.path()).unwrap().records, 0);
+    }
+
+    #[test]
+    fn detect_phred_offset() {
+        assert_eq!(
+            super::detect_phred_offset(crate::tests::generate_fastq(42, 1_000, 150), 100).unwrap(),
+            33
+        );
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"@1\nACGT\n+\n@Ih^\n@2\nACGT\n+\nBBBB\n")
+            .unwrap();
+        assert_eq!(super::detect_phred_offset(file.path(), 100).unwrap(), 64);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"@1\nACGT\n+\nAAAA\n@2\nACGT\n+\n!!!!\n")
+            .unwrap();
+        assert!(matches!(
+            super::detect_phred_offset(file.path(), 1),
+            Err(error::Error::AmbiguousPhred { min: 65, max: 65 })
+        ));
+    }
+
+    #[test]
+    fn keep() {
+        let r1 = block::Record {
+            comment: b"@read/1",
+            sequence: b"",
+            plus: b"",
+            quality: b"",
+        };
+        let r2 = block::Record {
+            comment: b"@read/2 other",
+            sequence: b"",
+            plus: b"",
+            quality: b"",
+        };
+
+        for seed in 0..100 {
+            assert_eq!(super::keep(&r1, 0.5, seed), super::keep(&r2, 0.5, seed));
+        }
+        assert!(super::keep(&r1, 1.0, 42));
+        assert!(!super::keep(&r1, 0.0, 42));
+    }
+
+    #[test]
+    fn subsample() {
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+        let mut output = Vec::new();
+        let nb_record = super::subsample(file.path(), 0.1, 42, &mut output).unwrap();
+
+        assert!(nb_record > 50 && nb_record < 150);
+        assert_eq!(count_records(&output) as u64, nb_record);
+
+        let mut again = Vec::new();
+        super::subsample(file.path(), 0.1, 42, &mut again).unwrap();
+        assert_eq!(output, again);
+
+        let mut other = Vec::new();
+        super::subsample(file.path(), 0.1, 24, &mut other).unwrap();
+        assert_ne!(output, other);
+    }
+
+    #[test]
+    fn filter() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"@1\nACGTACGT\n+\nIIIIIIII\n@2\nACGT\n+\nIIII\n@3\nACGTACGT\n+\n!!!!!!!!\n@4\nACGTACGN\n+\n5555555I\n")
+            .unwrap();
+
+        let mut output = Vec::new();
+        let nb_record = super::filter(file.path(), &mut output, |record| {
+            record.sequence.len() >= 8 && record.mean_quality(33) >= 20.0
+        })
+        .unwrap();
+
+        assert_eq!(nb_record, 2);
+        assert_eq!(
+            output,
+            b"@1\nACGTACGT\n+\nIIIIIIII\n@4\nACGTACGN\n+\n5555555I\n".to_vec()
+        );
+
+        let mut output = Vec::new();
+        assert_eq!(
+            super::filter(file.path(), &mut output, |record| record.count_n() == 0).unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn interleave() {
+        let r1 = crate::tests::generate_fastq(42, 1_000, 150);
+        let r2 = crate::tests::generate_fastq(43, 1_000, 100);
+        let output = tempfile::NamedTempFile::new().unwrap();
+
+        super::interleave(
+            r1.path(),
+            r2.path(),
+            std::io::BufWriter::new(output.reopen().unwrap()),
+        )
+        .unwrap();
+
+        let mut lengths = Vec::new();
+        for block in interleaved::Producer::new(output.path()).unwrap() {
+            let mut reader = interleaved::Reader::new(block.unwrap());
+            while let Some((mate1, mate2)) = reader.next_record().unwrap() {
+                assert_eq!(mate1.comment, mate2.comment);
+                lengths.push((mate1.sequence.len(), mate2.sequence.len()));
+            }
+        }
+        assert_eq!(lengths, vec![(150, 100); 1_000]);
+
+        let r2 = crate::tests::generate_fastq(43, 999, 100);
+        assert!(matches!(
+            super::interleave(r1.path(), r2.path(), std::io::sink()),
+            Err(error::Error::UnpairedRecords)
+        ));
+    }
+
+    #[test]
+    fn deinterleave() {
+        let r1 = crate::tests::generate_fastq(42, 1_000, 150);
+        let r2 = crate::tests::generate_fastq(43, 1_000, 100);
+        let interleaved = tempfile::NamedTempFile::new().unwrap();
+
+        super::interleave(r1.path(), r2.path(), interleaved.reopen().unwrap()).unwrap();
+
+        let mut out1 = Vec::new();
+        let mut out2 = Vec::new();
+        super::deinterleave(interleaved.path(), &mut out1, &mut out2).unwrap();
+
+        assert_eq!(out1, std::fs::read(r1.path()).unwrap());
+        assert_eq!(out2, std::fs::read(r2.path()).unwrap());
+
+        let odd = crate::tests::generate_fastq(42, 999, 150);
+        assert!(matches!(
+            super::deinterleave(odd.path(), std::io::sink(), std::io::sink()),
+            Err(error::Error::UnpairedRecords)
+        ));
+    }
+
+    #[test]
+    fn split() {
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let out_dir = tempfile::tempdir().unwrap();
+
+        let paths = super::split(file.path(), 7, out_dir.path()).unwrap();
+
+        assert_eq!(paths.len(), 7);
+        assert_eq!(paths[6], out_dir.path().join("chunk_6.fastq"));
+
+        let mut data = Vec::new();
+        for path in paths {
+            let chunk = std::fs::read(path).unwrap();
+            assert_eq!(chunk[0], b'@');
+            assert_eq!(
+                count_records(&chunk) * 4,
+                chunk.iter().filter(|c| **c == b'\n').count()
+            );
+            data.extend(chunk);
+        }
+
+        assert_eq!(data, std::fs::read(file.path()).unwrap());
+    }
+
+    #[test]
+    fn for_each() {
+        let mut nb_record = 0;
+        super::for_each(crate::tests::generate_fastq(42, 1_000, 150), |_| {
+            nb_record += 1
+        })
+        .unwrap();
+
+        assert_eq!(nb_record, 1_000);
+    }
+
+    #[test]
+    fn try_for_each() {
+        let mut nb_record = 0;
+        let result = super::try_for_each(crate::tests::generate_fastq(42, 1_000, 150), |_| {
+            nb_record += 1;
+            if nb_record == 10 {
+                Err(error::Error::Worker {
+                    source: "stop".into(),
+                })
+            } else {
+                Ok(())
+            }
+        });
+
+        assert!(matches!(result, Err(error::Error::Worker { .. })));
+        assert_eq!(nb_record, 10);
+    }
+
+    #[test]
+    fn find_map() {
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+        let mut nb_record = 0;
+        let found = super::find_map(file.path(), |record| {
+            nb_record += 1;
+            record.id().ends_with(b"42").then(|| record.id().to_vec())
+        })
+        .unwrap();
+        assert_eq!(found, Some(b"42".to_vec()));
+        assert_eq!(nb_record, 43);
+
+        assert_eq!(
+            super::find_map(file.path(), |record| record.sequence.is_empty().then(|| ())).unwrap(),
+            None
+        );
+
+        let found = super::par_find_map(file.path(), |record| {
+            record.id().ends_with(b"42").then(|| record.id().to_vec())
+        })
+        .unwrap()
+        .unwrap();
+        assert!(found.ends_with(b"42"));
+
+        assert_eq!(
+            super::par_find_map(file.path(), |record| record.sequence.is_empty().then(|| ()))
+                .unwrap(),
+            None
+        );
+
+        let mut truncated = tempfile::NamedTempFile::new().unwrap();
+        truncated.write_all(b"@1\nACGT\n+\n!!!!\n@2\nAC").unwrap();
+        assert!(super::par_find_map(truncated.path(), |_| None::<()>).is_err());
+    }
+
     mod reader {
         use super::*;
 
+        #[test]
+        fn from_slice() {
+            let mut reader = Reader::new(block::Block::from_slice(
+                b"@1\nACGT\n+\n!!!!\n@2\nTGCA\n+\n####\n",
+            ));
+
+            let record = reader.next_record().unwrap().unwrap();
+            assert_eq!(record.comment, b"@1");
+            assert_eq!(record.sequence, b"ACGT");
+
+            let record = reader.next_record().unwrap().unwrap();
+            assert_eq!(record.comment, b"@2");
+            assert_eq!(record.quality, b"####");
+
+            assert!(reader.next_record().unwrap().is_none());
+        }
+
+        #[test]
+        fn uniform_length() {
+            let mut data = Vec::new();
+            for i in 0..100 {
+                data.extend(format!("@{}\nACGTACGT\n+{}\n!!!!!!!!\n", i, i).bytes());
+            }
+            data.extend(b"@short\nACGT\n+\n!!!!\n@last\nTTTTTTTT\n+\n########");
+            let block = block::Block::from_slice(&data);
+
+            let mut expected = Vec::new();
+            Reader::new_validated(block.clone())
+                .for_each(|record| expected.push(record.to_owned()))
+                .unwrap();
+
+            let mut reader = Reader::new(block.clone());
+            let mut records = Vec::new();
+            for _ in 0..block::UNIFORM_PROBE {
+                assert_eq!(reader.uniform_length(), None);
+                records.push(reader.next_record().unwrap().unwrap().to_owned());
+            }
+            assert_eq!(reader.uniform_length(), Some(8));
+
+            reader
+                .for_each(|record| records.push(record.to_owned()))
+                .unwrap();
+            assert_eq!(reader.uniform_length(), None);
+            assert_eq!(records, expected);
+            assert_eq!(records.len(), 102);
+
+            let mut reader = Reader::new_validated(block);
+            reader.for_each(|_| ()).unwrap();
+            assert_eq!(reader.uniform_length(), None);
+        }
+
+        #[test]
+        fn uniform_length_short_quality() {
+            let mut data = Vec::new();
+            for i in 0..block::UNIFORM_PROBE {
+                data.extend(format!("@{}\nACGT\n+\n!!!!\n", i).bytes());
+            }
+            data.extend(b"@short\nACGT\n+\n!!\n@\nACGT\n+\n!!!!\n@last\nACGT\n+\n####\n");
+
+            let mut reader = Reader::new(block::Block::from_slice(&data));
+            let mut records = Vec::new();
+            reader
+                .for_each(|record| records.push(record.to_owned()))
+                .unwrap();
+
+            assert_eq!(reader.uniform_length(), None);
+            assert_eq!(records.len(), block::UNIFORM_PROBE + 3);
+
+            let short = &records[block::UNIFORM_PROBE];
+            assert_eq!(short.comment, b"@short");
+            assert_eq!(short.quality, b"!!");
+            assert_eq!(records[block::UNIFORM_PROBE + 1].comment, b"@");
+            assert_eq!(records[block::UNIFORM_PROBE + 2].comment, b"@last");
+        }
+
+        #[test]
+        fn no_trailing_newline() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(b"@1\nACGT\n+\n!!!!\n@2\nTGCA\n+\n####")
+                .unwrap();
+
+            let mut producer = Producer::new(file.path()).unwrap();
+            let mut reader = Reader::new_validated(producer.next_block().unwrap().unwrap());
+
+            assert_eq!(reader.next_record().unwrap().unwrap().comment, b"@1");
+            let record = reader.next_record().unwrap().unwrap();
+            assert_eq!(record.comment, b"@2");
+            assert_eq!(record.quality, b"####");
+            assert!(reader.next_record().unwrap().is_none());
+            assert!(reader.next_record().unwrap().is_none());
+        }
+
+        #[test]
+        fn skip_blank_lines() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            file.write_all(b"@1\nACGT\n+\n!!!!\n\n@2\nTGCA\n+\n####\n\n\n@3\nAAAA\n+\n$$$$\n\n")
+                .unwrap();
+
+            let mut producer = Producer::with_blocksize(20, file.path()).unwrap();
+
+            let mut comments = Vec::new();
+            while let Some(block) = producer.next_block().unwrap() {
+                let mut reader = Reader::new_validated(block);
+                reader.set_skip_blank_lines(true);
+                reader
+                    .for_each(|record| comments.push(record.comment.to_vec()))
+                    .unwrap();
+            }
+
+            assert_eq!(
+                comments,
+                vec![b"@1".to_vec(), b"@2".to_vec(), b"@3".to_vec()]
+            );
+
+            let mut reader =
+                Reader::new_validated(block::Block::from_slice(b"\n@1\nACGT\n+\n!!!!\n"));
+            assert!(reader.next_record().is_err());
+
+            let mut reader =
+                Reader::new_validated(block::Block::from_slice(b"@1\nACGT\n\n+\n!!!!\n"));
+            reader.set_skip_blank_lines(true);
+            assert!(reader.next_record().is_err());
+        }
+
+        #[test]
+        fn file_offset() {
+            let mut producer =
+                Producer::with_blocksize(1_000, crate::tests::generate_fastq(42, 10, 150)).unwrap();
+            let data = std::fs::read(crate::tests::generate_fastq(42, 10, 150).path()).unwrap();
+
+            producer.next_block().unwrap();
+            let mut reader = Reader::new(producer.next_block().unwrap().unwrap());
+            assert_eq!(reader.offset(), 0);
+            assert_eq!(reader.file_offset(), reader.block().file_offset());
+
+            let position = reader.file_offset() as usize;
+            let record = reader.next_record().unwrap().unwrap().to_owned();
+            assert_eq!(
+                &data[position..position + record.comment.len()],
+                &record.comment[..]
+            );
+            assert_eq!(
+                reader.offset(),
+                record.comment.len()
+                    + record.sequence.len()
+                    + record.plus.len()
+                    + record.quality.len()
+                    + 4
+            );
+        }
+
+        #[test]
+        fn alphabet() {
+            let mut reader = Reader::new(block::Block::from_slice(
+                b"@1\nACGT\n+\n!!!!\n@2\nACXT\n+\n!!!!\n",
+            ));
+            reader.set_alphabet(Some(b"ACGTN"));
+
+            assert!(reader.next_record().unwrap().is_some());
+            assert!(matches!(
+                reader.next_record(),
+                Err(error::Error::InvalidBase {
+                    byte: b'X',
+                    offset: 20
+                })
+            ));
+
+            let mut reader = LenientReader::new(block::Block::from_slice(
+                b"@1\nACGT\n+\n!!!!\n@2\nACXT\n+\n!!!!\n@3\nNNNN\n+\n!!!!\n",
+            ));
+            reader.set_alphabet(Some(b"ACGTN"));
+
+            assert_eq!(reader.next_record().unwrap().comment, b"@1");
+            assert_eq!(reader.next_record().unwrap().comment, b"@3");
+            assert_eq!(reader.nb_skipped(), 1);
+
+            let mut reader = Reader::new(block::Block::from_slice(b"@1\nACXT\n+\n!!!!\n"));
+            assert!(reader.next_record().unwrap().is_some());
+        }
+
+        #[test]
+        fn lenient() {
+            let mut reader = LenientReader::new(block::Block::from_slice(
+                b"@1\nACGT\n+\n!!!!\n@2\nACGT\n+\n!!!\n@3\nAAAA\n+\n$$$$\n@4\nAC",
+            ));
+
+            let mut comments = Vec::new();
+            while let Some(record) = reader.next_record() {
+                comments.push(record.comment.to_vec());
+            }
+
+            assert_eq!(comments, vec![b"@1".to_vec(), b"@3".to_vec()]);
+            assert_eq!(reader.nb_skipped(), 2);
+            assert_eq!(reader.skipped(), &[15..29, 44..49]);
+        }
+
+        #[test]
+        fn records() {
+            let mut reader = Reader::new(block::Block::from_slice(
+                b"@1\nACGT\n+\n!!!!\n@2\nTGCA\n+\n####\n@3\nAAAA\n+\n$$$$\n",
+            ));
+
+            let comments = reader
+                .records()
+                .filter_map(|record| record.ok())
+                .filter(|record| record.sequence != b"TGCA")
+                .map(|record| record.comment)
+                .take(5)
+                .collect::<Vec<&[u8]>>();
+
+            assert_eq!(comments, vec![b"@1", b"@3"]);
+        }
+
+        #[test]
+        fn records_stop_on_error() {
+            let mut reader = Reader::new_validated(block::Block::from_slice(
+                b"@1\nACGT\n+\n!!!!\n@2\nTGCA\n+\n###\n@3\nAAAA\n+\n$$$$\n",
+            ));
+
+            let results = reader
+                .records()
+                .collect::<Vec<error::Result<block::Record>>>();
+
+            assert_eq!(results.len(), 2);
+            assert!(results[0].is_ok());
+            assert!(matches!(
+                results[1],
+                Err(error::Error::SeqQualLengthMismatch { offset: 15, .. })
+            ));
+        }
+
+        #[test]
+        fn for_each() {
+            let mut reader = Reader::new(block::Block::from_slice(
+                b"@1\nACGT\n+\n!!!!\n@2\nTGCA\n+\n####\n",
+            ));
+
+            let mut nb_base = 0;
+            reader
+                .for_each(|record| nb_base += record.sequence.len())
+                .unwrap();
+
+            assert_eq!(nb_base, 8);
+
+            let mut reader = Reader::new(block::Block::from_slice(b"@1\nACGT\n+"));
+            assert!(matches!(
+                reader.for_each(|_| ()),
+                Err(error::Error::PartialRecord { .. })
+            ));
+        }
+
         #[test]
         fn iterate_over_seq() {
             let mut producer =
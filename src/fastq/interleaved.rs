@@ -0,0 +1,94 @@
+//! Struct that extract part of an interleaved fastq file, mate of a pair are never split in two block.
+
+/* crate use */
+
+/* project use */
+use crate::block;
+use crate::error;
+use crate::fastq;
+
+impl_producer!(
+    Producer,
+    |block: &[u8]| {
+        let end = fastq::Producer::correct_block_size(block)? as usize;
+
+        let nb_record = fastq::count_records(&block[..end]);
+        let end = fastq::records_end(&block[..end], nb_record - nb_record % 2);
+
+        if end == 0 {
+            Err(error::Error::NoNewLineInBlock { offset: 0 })
+        } else {
+            Ok(end as u64)
+        }
+    },
+    // pair boundary can't be detected, seek_to should only be used with a saved position
+    fastq::find_record_start,
+    fastq::Producer::truncated_record
+);
+
+/// Read records of an interleaved fastq [Block](block::Block) two by two
+pub struct Reader {
+    offset: usize,
+    block: block::Block,
+}
+
+impl Reader {
+    pub fn new(block: block::Block) -> Self {
+        Reader { offset: 0, block }
+    }
+
+    pub fn next_record(&mut self) -> error::Result<Option<(block::Record<'_>, block::Record<'_>)>> {
+        match fastq::Reader::read_record(&self.block, &mut self.offset)? {
+            None => Ok(None),
+            Some(r1) => match fastq::Reader::read_record(&self.block, &mut self.offset)? {
+                Some(r2) => Ok(Some((r1, r2))),
+                None => Err(error::Error::UnpairedRecords),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn even_block() {
+        let mut producer =
+            Producer::with_blocksize(1_000, crate::tests::generate_fastq(42, 1_000, 150)).unwrap();
+
+        let mut nb_pair = 0;
+        while let Some(block) = producer.next_block().unwrap() {
+            assert_eq!(fastq::count_records(block.data()) % 2, 0);
+
+            let mut reader = Reader::new(block);
+            while let Some((r1, r2)) = reader.next_record().unwrap() {
+                assert_eq!(r1.sequence.len(), r2.sequence.len());
+                nb_pair += 1;
+            }
+        }
+
+        assert_eq!(nb_pair, 500);
+    }
+
+    #[test]
+    fn odd_record() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"@1/1\nA\n+\n!\n@1/2\nT\n+\n!\n@2/1\nC\n+\n!\n")
+            .unwrap();
+
+        let mut producer = Producer::new(file.path()).unwrap();
+        let mut reader = Reader::new(producer.next_block().unwrap().unwrap());
+
+        let (r1, r2) = reader.next_record().unwrap().unwrap();
+        assert_eq!(r1.comment, b"@1/1");
+        assert_eq!(r2.comment, b"@1/2");
+
+        assert!(matches!(
+            reader.next_record(),
+            Err(error::Error::UnpairedRecords)
+        ));
+    }
+}
@@ -0,0 +1,134 @@
+//! Struct that extract synchronized blocks from two paired fastq files.
+
+/* crate use */
+
+/* project use */
+use crate::block;
+use crate::error;
+use crate::fastq;
+
+/// Produce pair of [Block](block::Block), each block of a pair contains the same number of records
+pub struct Producer {
+    r1: fastq::Producer,
+    r2: fastq::Producer,
+}
+
+impl Producer {
+    /// Create a new paired Block producer
+    #[inline(always)]
+    pub fn new<P>(r1: P, r2: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, r1, r2)
+    }
+
+    pub fn with_blocksize<P>(blocksize: u64, r1: P, r2: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Ok(Self {
+            r1: fastq::Producer::with_blocksize(blocksize, r1)?,
+            r2: fastq::Producer::with_blocksize(blocksize, r2)?,
+        })
+    }
+
+    pub fn next_block(&mut self) -> error::Result<Option<(block::Block, block::Block)>> {
+        let r1_offset = self.r1.offset();
+        let r2_offset = self.r2.offset();
+
+        match (self.r1.next_block()?, self.r2.next_block()?) {
+            (None, None) => Ok(None),
+            (Some(mut r1_block), Some(mut r2_block)) => {
+                let nb_record = fastq::count_records(r1_block.data())
+                    .min(fastq::count_records(r2_block.data()));
+
+                let r1_end = fastq::records_end(r1_block.data(), nb_record);
+                r1_block.truncate(r1_end);
+                self.r1.set_offset(r1_offset + r1_end as u64);
+
+                let r2_end = fastq::records_end(r2_block.data(), nb_record);
+                r2_block.truncate(r2_end);
+                self.r2.set_offset(r2_offset + r2_end as u64);
+
+                Ok(Some((r1_block, r2_block)))
+            }
+            _ => Err(error::Error::UnpairedRecords),
+        }
+    }
+}
+
+impl Iterator for Producer {
+    type Item = error::Result<(block::Block, block::Block)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_block() {
+            Ok(Some(blocks)) => Some(Ok(blocks)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Read records of a pair of [Block](block::Block) together
+pub struct Reader {
+    r1: fastq::Reader,
+    r2: fastq::Reader,
+}
+
+impl Reader {
+    pub fn new(blocks: (block::Block, block::Block)) -> Self {
+        Self {
+            r1: fastq::Reader::new(blocks.0),
+            r2: fastq::Reader::new(blocks.1),
+        }
+    }
+
+    pub fn next_record(&mut self) -> error::Result<Option<(block::Record<'_>, block::Record<'_>)>> {
+        match (self.r1.next_record()?, self.r2.next_record()?) {
+            (None, None) => Ok(None),
+            (Some(r1), Some(r2)) => Ok(Some((r1, r2))),
+            _ => Err(error::Error::UnpairedRecords),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synchronized_blocks() {
+        let r1 = crate::tests::generate_fastq(42, 1_000, 150);
+        let r2 = crate::tests::generate_fastq(43, 1_000, 100);
+
+        let mut producer = Producer::with_blocksize(4_000, r1.path(), r2.path()).unwrap();
+
+        let mut nb_record = 0;
+        while let Some(blocks) = producer.next_block().unwrap() {
+            let mut reader = Reader::new(blocks);
+
+            while let Some((r1, r2)) = reader.next_record().unwrap() {
+                assert_eq!(r1.comment, r2.comment);
+                assert_eq!(r1.sequence.len(), 150);
+                assert_eq!(r2.sequence.len(), 100);
+                nb_record += 1;
+            }
+        }
+
+        assert_eq!(nb_record, 1_000);
+    }
+
+    #[test]
+    fn unpaired_records() {
+        let r1 = crate::tests::generate_fastq(42, 1_000, 150);
+        let r2 = crate::tests::generate_fastq(43, 999, 150);
+
+        let producer = Producer::with_blocksize(4_000, r1.path(), r2.path()).unwrap();
+
+        assert!(matches!(
+            producer.last(),
+            Some(Err(error::Error::UnpairedRecords))
+        ));
+    }
+}
@@ -0,0 +1,91 @@
+//! Compute coordinates of quality trimmed records, slice sequence and quality with result to get trimmed record.
+
+/* project use */
+use crate::block;
+
+/// Trimmomatic like sliding window trimming, return start and end of trimmed record
+///
+/// Record start at first window with a mean quality greater or equal to min_mean_q. Record is cut at the first following window with a lower mean quality, bases of this window are add back while their quality is greater or equal to min_mean_q. If no window pass threshold, (0, 0) is returned.
+pub fn sliding_window(
+    record: &block::Record,
+    window: usize,
+    min_mean_q: f64,
+    offset: u8,
+) -> (usize, usize) {
+    let scores = record.quality_scores(offset).collect::<Vec<u8>>();
+    if scores.is_empty() {
+        return (0, 0);
+    }
+
+    let window = window.clamp(1, scores.len());
+    let threshold = min_mean_q * window as f64;
+
+    let mut sum = scores[..window].iter().map(|q| *q as u64).sum::<u64>();
+    let mut start = None;
+
+    for begin in 0..=scores.len() - window {
+        if begin != 0 {
+            sum = sum + scores[begin + window - 1] as u64 - scores[begin - 1] as u64;
+        }
+
+        let pass = sum as f64 >= threshold;
+        match start {
+            None if pass => start = Some(begin),
+            Some(start) if !pass => {
+                let end = scores[begin..]
+                    .iter()
+                    .position(|q| (*q as f64) < min_mean_q)
+                    .map_or(scores.len(), |pos| begin + pos);
+
+                return (start, end);
+            }
+            _ => (),
+        }
+    }
+
+    start.map_or((0, 0), |start| (start, scores.len()))
+}
+
+/// Remove bases at 3' end with a quality lower than q, return end of trimmed record
+pub fn trim_trailing_below(record: &block::Record, q: u8, offset: u8) -> usize {
+    record
+        .quality
+        .iter()
+        .rposition(|c| c.saturating_sub(offset) >= q)
+        .map_or(0, |pos| pos + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(quality: &[u8]) -> block::Record<'_> {
+        block::Record {
+            comment: b"@1",
+            sequence: &b"ACGTACGTACGTACGTACGT"[..quality.len()],
+            plus: b"+",
+            quality,
+        }
+    }
+
+    #[test]
+    fn sliding() {
+        // scores 0 0 30 30 30 30 30 30 10 30 30 0 0
+        let quality = b"!!??????+??!!";
+
+        assert_eq!(sliding_window(&record(quality), 4, 20.0, 33), (1, 8));
+        assert_eq!(sliding_window(&record(quality), 2, 20.0, 33), (2, 11));
+        assert_eq!(sliding_window(&record(quality), 1, 20.0, 33), (2, 8));
+        assert_eq!(sliding_window(&record(quality), 4, 35.0, 33), (0, 0));
+        assert_eq!(sliding_window(&record(b"????"), 10, 20.0, 33), (0, 4));
+        assert_eq!(sliding_window(&record(b""), 4, 20.0, 33), (0, 0));
+    }
+
+    #[test]
+    fn trailing() {
+        assert_eq!(trim_trailing_below(&record(b"??+?#!!"), 20, 33), 4);
+        assert_eq!(trim_trailing_below(&record(b"????"), 20, 33), 4);
+        assert_eq!(trim_trailing_below(&record(b"!!!!"), 20, 33), 0);
+        assert_eq!(trim_trailing_below(&record(b""), 20, 33), 0);
+    }
+}
@@ -0,0 +1,860 @@
+//! Detect if a file is a fasta or a fastq and build the corresponding producer.
+
+/* std use */
+use std::io::BufRead;
+
+/* project use */
+use crate::block;
+use crate::error;
+use crate::fasta;
+use crate::fastq;
+
+/* mod declaration */
+pub mod jsonl;
+
+/// Format of a fastx file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Fasta,
+    Fastq,
+}
+
+impl Format {
+    /// Get format associate to the first byte of a record
+    pub fn from_byte(byte: u8) -> error::Result<Self> {
+        match byte {
+            b'>' => Ok(Format::Fasta),
+            b'@' => Ok(Format::Fastq),
+            _ => Err(error::Error::UnknownFormat),
+        }
+    }
+
+    /// Detect format of file by peeking the first non-whitespace byte, a [UTF-8 byte order mark](block::UTF8_BOM) at begin of file is skipped
+    pub fn detect<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let mut reader = std::io::BufReader::new(
+            std::fs::File::open(path).map_err(|source| error::Error::OpenFile { source })?,
+        );
+
+        if reader
+            .fill_buf()
+            .map_err(|source| error::Error::ReadFile { source })?
+            .starts_with(&block::UTF8_BOM)
+        {
+            reader.consume(block::UTF8_BOM.len());
+        }
+
+        loop {
+            let buffer = reader
+                .fill_buf()
+                .map_err(|source| error::Error::ReadFile { source })?;
+
+            if buffer.is_empty() {
+                return Err(error::Error::UnknownFormat);
+            }
+
+            if let Some(byte) = buffer.iter().find(|c| !c.is_ascii_whitespace()) {
+                return Self::from_byte(*byte);
+            }
+
+            let length = buffer.len();
+            reader.consume(length);
+        }
+    }
+}
+
+/// Compression of a file, detected by magic bytes at start of file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    /// Get compression associate to the first bytes of a file, data without known magic bytes isn't compressed
+    pub fn from_magic(data: &[u8]) -> Self {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if data.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if data.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Detect compression of file by peeking its first bytes, extension of file is ignored
+    pub fn detect<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let mut file =
+            std::fs::File::open(path).map_err(|source| error::Error::OpenFile { source })?;
+
+        let mut magic = [0; 6];
+        let mut length = 0;
+        while length < magic.len() {
+            match std::io::Read::read(&mut file, &mut magic[length..]) {
+                Ok(0) => break,
+                Ok(n) => length += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(source) => return Err(error::Error::ReadFile { source }),
+            }
+        }
+
+        Ok(Self::from_magic(&magic[..length]))
+    }
+}
+
+/// A Block producer for fasta or fastq file
+pub enum Producer {
+    Fasta(fasta::Producer),
+    Fastq(fastq::Producer),
+    #[cfg(feature = "zstd")]
+    Zstd(crate::zstd::Producer),
+}
+
+impl Producer {
+    /// Detect format of file and build the corresponding producer, blocksize is choose by [default_blocksize](crate::default_blocksize)
+    pub fn new<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        open(path).map(|(_, producer)| producer)
+    }
+
+    /// Detect format of file and build the corresponding producer with a specific blocksize
+    pub fn with_blocksize<P>(blocksize: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        open_with_blocksize(blocksize, path).map(|(_, producer)| producer)
+    }
+
+    /// Get format of producer
+    pub fn format(&self) -> Format {
+        match self {
+            Producer::Fasta(_) => Format::Fasta,
+            Producer::Fastq(_) => Format::Fastq,
+            #[cfg(feature = "zstd")]
+            Producer::Zstd(producer) => producer.format(),
+        }
+    }
+
+    pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+        match self {
+            Producer::Fasta(producer) => producer.next_block(),
+            Producer::Fastq(producer) => producer.next_block(),
+            #[cfg(feature = "zstd")]
+            Producer::Zstd(producer) => producer.next_block(),
+        }
+    }
+}
+
+impl Iterator for Producer {
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Producer::Fasta(producer) => producer.next(),
+            Producer::Fastq(producer) => producer.next(),
+            #[cfg(feature = "zstd")]
+            Producer::Zstd(producer) => producer.next(),
+        }
+    }
+}
+
+/// A record of a fasta or a fastq file, plus and quality of a fasta record are empty
+pub enum FastxRecord<'a> {
+    Fasta(block::Record<'a>),
+    Fastq(block::Record<'a>),
+}
+
+impl<'a> FastxRecord<'a> {
+    /// Get format of file that contains record
+    pub fn format(&self) -> Format {
+        match self {
+            FastxRecord::Fasta(_) => Format::Fasta,
+            FastxRecord::Fastq(_) => Format::Fastq,
+        }
+    }
+
+    /// Get record without its format
+    pub fn record(&self) -> &block::Record<'a> {
+        match self {
+            FastxRecord::Fasta(record) | FastxRecord::Fastq(record) => record,
+        }
+    }
+
+    /// Convert in record without its format
+    pub fn into_record(self) -> block::Record<'a> {
+        match self {
+            FastxRecord::Fasta(record) | FastxRecord::Fastq(record) => record,
+        }
+    }
+}
+
+/// Read records of a fasta or fastq [Block](block::Block)
+pub enum Reader {
+    Fasta(fasta::Reader),
+    Fastq(fastq::Reader),
+}
+
+impl Reader {
+    /// Create a reader, format is detect by the first non-whitespace byte of block, an empty block is read as fasta
+    pub fn new(block: block::Block) -> Self {
+        match block.data().iter().find(|c| !c.is_ascii_whitespace()) {
+            Some(b'@') => Self::with_format(Format::Fastq, block),
+            _ => Self::with_format(Format::Fasta, block),
+        }
+    }
+
+    /// Create a reader of block in format
+    pub fn with_format(format: Format, block: block::Block) -> Self {
+        match format {
+            Format::Fasta => Reader::Fasta(fasta::Reader::new(block)),
+            Format::Fastq => Reader::Fastq(fastq::Reader::new(block)),
+        }
+    }
+
+    /// Get format of reader
+    pub fn format(&self) -> Format {
+        match self {
+            Reader::Fasta(_) => Format::Fasta,
+            Reader::Fastq(_) => Format::Fastq,
+        }
+    }
+
+    pub fn next_record(&mut self) -> error::Result<Option<FastxRecord<'_>>> {
+        match self {
+            Reader::Fasta(reader) => Ok(reader.next_record()?.map(FastxRecord::Fasta)),
+            Reader::Fastq(reader) => Ok(reader.next_record()?.map(FastxRecord::Fastq)),
+        }
+    }
+}
+
+/// Detect compression and format of file and build the corresponding producer, blocksize is choose by [default_blocksize](crate::default_blocksize)
+pub fn open<P>(path: P) -> error::Result<(Format, Producer)>
+where
+    P: AsRef<std::path::Path>,
+{
+    let compression = Compression::detect(&path)?;
+
+    build(compression, None, path)
+}
+
+/// Detect compression and format of file and build the corresponding producer with a specific blocksize
+pub fn open_with_blocksize<P>(blocksize: u64, path: P) -> error::Result<(Format, Producer)>
+where
+    P: AsRef<std::path::Path>,
+{
+    let compression = Compression::detect(&path)?;
+
+    build(compression, Some(blocksize), path)
+}
+
+/// Detect format of file and build the corresponding producer, compression isn't detected file is read as compressed by compression
+pub fn open_with_compression<P>(
+    compression: Compression,
+    path: P,
+) -> error::Result<(Format, Producer)>
+where
+    P: AsRef<std::path::Path>,
+{
+    build(compression, None, path)
+}
+
+fn build<P>(
+    compression: Compression,
+    blocksize: Option<u64>,
+    path: P,
+) -> error::Result<(Format, Producer)>
+where
+    P: AsRef<std::path::Path>,
+{
+    #[cfg(feature = "zstd")]
+    if compression == Compression::Zstd {
+        let producer = match blocksize {
+            Some(blocksize) => crate::zstd::Producer::with_blocksize(blocksize, path)?,
+            None => crate::zstd::Producer::new(path)?,
+        };
+
+        return Ok((producer.format(), Producer::Zstd(producer)));
+    }
+
+    if compression != Compression::None {
+        return Err(error::Error::CompressedFile { compression });
+    }
+
+    let format = Format::detect(&path)?;
+
+    let producer = match (format, blocksize) {
+        (Format::Fasta, None) => Producer::Fasta(fasta::Producer::new(path)?),
+        (Format::Fasta, Some(blocksize)) => {
+            Producer::Fasta(fasta::Producer::with_blocksize(blocksize, path)?)
+        }
+        (Format::Fastq, None) => Producer::Fastq(fastq::Producer::new(path)?),
+        (Format::Fastq, Some(blocksize)) => {
+            Producer::Fastq(fastq::Producer::with_blocksize(blocksize, path)?)
+        }
+    };
+
+    Ok((format, producer))
+}
+
+/// Write each record of fastq input in fasta format in output, sequence isn't wrap, return number of records
+pub fn fastq_to_fasta<P, W>(input: P, output: W) -> error::Result<u64>
+where
+    P: AsRef<std::path::Path>,
+    W: std::io::Write,
+{
+    let mut writer = fasta::Writer::with_line_width(0, output);
+    let mut nb_record = 0;
+
+    fastq::try_for_each(input, |record| {
+        nb_record += 1;
+        writer.write(&record)
+    })?;
+
+    writer
+        .into_inner()
+        .flush()
+        .map_err(|source| error::Error::WriteFile { source })?;
+
+    Ok(nb_record)
+}
+
+/// Write each record of input, fasta or fastq, in JSON lines format, records are stream block by block, return number of records
+pub fn to_jsonl<P, W>(input: P, output: W, quality: jsonl::QualityEncoding) -> error::Result<u64>
+where
+    P: AsRef<std::path::Path>,
+    W: std::io::Write,
+{
+    let mut producer = Producer::new(input)?;
+    let mut writer = jsonl::Writer::new(quality, output);
+    let mut nb_record = 0;
+
+    while let Some(block) = producer.next_block()? {
+        let mut reader = Reader::with_format(producer.format(), block);
+
+        while let Some(record) = reader.next_record()? {
+            writer.write(record.record())?;
+            nb_record += 1;
+        }
+    }
+
+    writer
+        .into_inner()
+        .flush()
+        .map_err(|source| error::Error::WriteFile { source })?;
+
+    Ok(nb_record)
+}
+
+/// How [reduce_files_with] distribute work between threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    /// Files are parsed in parallel, each file is read by one thread, best for many small files
+    File,
+    /// Files are parsed one after another, blocks of each file are parsed in parallel, best for few large files
+    Block,
+}
+
+/// Fold records of many fasta or fastq files in one accumulator, files are parsed in parallel
+///
+/// Same as [reduce_files_with] with [Granularity::File].
+pub fn reduce_files<P, I, A, Init, F, M>(
+    paths: I,
+    init: Init,
+    fold: F,
+    merge: M,
+) -> (A, Vec<(std::path::PathBuf, error::Error)>)
+where
+    P: AsRef<std::path::Path> + Send,
+    I: IntoIterator<Item = P>,
+    A: Send,
+    Init: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, block::Record) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    reduce_files_with(Granularity::File, paths, init, fold, merge)
+}
+
+/// Fold records of many fasta or fastq files in one accumulator, accumulators build by init are merged by merge
+///
+/// Parsing didn't stop at first error, records of a file that failed aren't merge in result, path of each file that failed are returned with error.
+pub fn reduce_files_with<P, I, A, Init, F, M>(
+    granularity: Granularity,
+    paths: I,
+    init: Init,
+    fold: F,
+    merge: M,
+) -> (A, Vec<(std::path::PathBuf, error::Error)>)
+where
+    P: AsRef<std::path::Path> + Send,
+    I: IntoIterator<Item = P>,
+    A: Send,
+    Init: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, block::Record) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    use rayon::iter::IntoParallelIterator;
+    use rayon::iter::ParallelIterator;
+
+    let paths = paths.into_iter().collect::<Vec<P>>();
+
+    let add = |(accumulator, mut errors): (A, Vec<_>), (path, result): (P, error::Result<A>)| {
+        match result {
+            Ok(other) => (merge(accumulator, other), errors),
+            Err(e) => {
+                errors.push((path.as_ref().to_path_buf(), e));
+                (accumulator, errors)
+            }
+        }
+    };
+
+    match granularity {
+        Granularity::File => paths
+            .into_par_iter()
+            .map(|path| {
+                let result = reduce_file(&path, &init, &fold);
+                (path, result)
+            })
+            .fold(|| (init(), Vec::new()), add)
+            .reduce(
+                || (init(), Vec::new()),
+                |(a, mut a_errors), (b, b_errors)| {
+                    a_errors.extend(b_errors);
+                    (merge(a, b), a_errors)
+                },
+            ),
+        Granularity::Block => paths
+            .into_iter()
+            .map(|path| {
+                let result = Producer::new(&path).and_then(|producer| match producer.format() {
+                    Format::Fasta => crate::parser::fold::fold(
+                        producer,
+                        fasta::Reader::read_record,
+                        &init,
+                        &fold,
+                        &merge,
+                    ),
+                    Format::Fastq => crate::parser::fold::fold(
+                        producer,
+                        fastq::Reader::read_record,
+                        &init,
+                        &fold,
+                        &merge,
+                    ),
+                });
+                (path, result)
+            })
+            .fold((init(), Vec::new()), add),
+    }
+}
+
+/// Recursively find files in dir with a name that end by one of extensions, symbolic links aren't follow
+///
+/// Same as [walk_with] with follow_links set to false.
+#[cfg(feature = "walkdir")]
+pub fn walk<P>(dir: P, extensions: &[&str]) -> impl Iterator<Item = std::path::PathBuf>
+where
+    P: AsRef<std::path::Path>,
+{
+    walk_with(dir, extensions, false)
+}
+
+/// Recursively find files in dir with a name that end by one of extensions, files of a directory are sorted by name
+///
+/// Extensions are given without leading dot and could contain many parts, `fq.gz` match `reads.fq.gz`, an empty extensions match all files. Entries that can't be read are skipped with a warning. Result could be send to [reduce_files] or `parse_files` of a shared state parser.
+#[cfg(feature = "walkdir")]
+pub fn walk_with<P>(
+    dir: P,
+    extensions: &[&str],
+    follow_links: bool,
+) -> impl Iterator<Item = std::path::PathBuf>
+where
+    P: AsRef<std::path::Path>,
+{
+    let suffixes = extensions
+        .iter()
+        .map(|extension| format!(".{}", extension))
+        .collect::<Vec<String>>();
+
+    walkdir::WalkDir::new(dir)
+        .follow_links(follow_links)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("in_place_fastx skip directory entry: {}", e);
+                None
+            }
+        })
+        .filter(|entry| entry.file_type().is_file())
+        .filter(move |entry| {
+            let name = entry.file_name().to_string_lossy();
+            suffixes.is_empty()
+                || suffixes
+                    .iter()
+                    .any(|suffix| name.ends_with(suffix.as_str()))
+        })
+        .map(|entry| entry.into_path())
+}
+
+/// Fold records of file in a new accumulator, file is read by current thread
+fn reduce_file<P, A, Init, F>(path: P, init: &Init, fold: &F) -> error::Result<A>
+where
+    P: AsRef<std::path::Path>,
+    Init: Fn() -> A,
+    F: Fn(&mut A, block::Record),
+{
+    let mut producer = Producer::new(path)?;
+    let mut accumulator = init();
+
+    while let Some(block) = producer.next_block()? {
+        let mut reader = Reader::with_format(producer.format(), block);
+
+        while let Some(record) = reader.next_record()? {
+            fold(&mut accumulator, record.into_record());
+        }
+    }
+
+    Ok(accumulator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    #[test]
+    fn detect_fasta() {
+        let (format, mut producer) = open(crate::tests::generate_fasta(42, 100, 150)).unwrap();
+
+        assert_eq!(format, Format::Fasta);
+        assert_eq!(producer.format(), Format::Fasta);
+        assert_eq!(producer.next_block().unwrap().unwrap().len(), 15490);
+        assert!(producer.next().is_none());
+    }
+
+    #[test]
+    fn detect_fastq() {
+        let (format, mut producer) = open(crate::tests::generate_fastq(42, 100, 150)).unwrap();
+
+        assert_eq!(format, Format::Fastq);
+        assert_eq!(producer.format(), Format::Fastq);
+        assert_eq!(producer.next_block().unwrap().unwrap().len(), 30980);
+        assert!(producer.next().is_none());
+    }
+
+    #[test]
+    fn detect_byte_order_mark() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&block::UTF8_BOM).unwrap();
+        file.write_all(b"@1\nACGT\n+\n!!!!\n").unwrap();
+
+        assert_eq!(Format::detect(file.path()).unwrap(), Format::Fastq);
+
+        let (format, mut producer) = open(file.path()).unwrap();
+        assert_eq!(format, Format::Fastq);
+
+        let block = producer.next_block().unwrap().unwrap();
+        assert_eq!(block.file_offset(), 3);
+        assert_eq!(block.records_fastq().count(), 1);
+    }
+
+    #[test]
+    fn compression() {
+        assert_eq!(
+            Compression::from_magic(&[0x1f, 0x8b, 0x08]),
+            Compression::Gzip
+        );
+        assert_eq!(Compression::from_magic(b"BZh91AY"), Compression::Bzip2);
+        assert_eq!(
+            Compression::from_magic(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]),
+            Compression::Xz
+        );
+        assert_eq!(
+            Compression::from_magic(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            Compression::Zstd
+        );
+        assert_eq!(Compression::from_magic(b"@1\nACGT"), Compression::None);
+        assert_eq!(Compression::from_magic(&[0x1f]), Compression::None);
+
+        // extension is ignored
+        let mut file = tempfile::Builder::new()
+            .suffix(".fastq")
+            .tempfile()
+            .unwrap();
+        file.write_all(&[0x1f, 0x8b, 0x08, 0x00]).unwrap();
+        assert_eq!(Compression::detect(file.path()).unwrap(), Compression::Gzip);
+        assert!(matches!(
+            open(file.path()),
+            Err(error::Error::CompressedFile {
+                compression: Compression::Gzip
+            })
+        ));
+
+        let mut file = tempfile::Builder::new().suffix(".gz").tempfile().unwrap();
+        file.write_all(b">1\nACGT\n").unwrap();
+        assert_eq!(Compression::detect(file.path()).unwrap(), Compression::None);
+        assert_eq!(open(file.path()).unwrap().0, Format::Fasta);
+
+        // forced compression override detection
+        assert!(open_with_compression(Compression::Zstd, file.path()).is_err());
+        assert_eq!(
+            open_with_compression(Compression::None, file.path())
+                .unwrap()
+                .0,
+            Format::Fasta
+        );
+    }
+
+    #[test]
+    fn fastq_to_fasta() {
+        let fastq = crate::tests::generate_fastq(42, 1_000, 150);
+        let fasta = tempfile::NamedTempFile::new().unwrap();
+
+        assert_eq!(
+            super::fastq_to_fasta(
+                fastq.path(),
+                std::io::BufWriter::new(fasta.reopen().unwrap())
+            )
+            .unwrap(),
+            1_000
+        );
+
+        let mut fastq_records = Vec::new();
+        fastq::for_each(fastq.path(), |record| {
+            fastq_records.push((record.id().to_vec(), record.sequence.to_vec()))
+        })
+        .unwrap();
+
+        let mut fasta_records = Vec::new();
+        fasta::for_each(fasta.path(), |record| {
+            assert_eq!(record.comment[0], b'>');
+            fasta_records.push((record.id().to_vec(), record.sequence.to_vec()))
+        })
+        .unwrap();
+
+        assert_eq!(fasta_records.len(), 1_000);
+        assert_eq!(fastq_records, fasta_records);
+    }
+
+    #[test]
+    fn reader() {
+        let mut reader = Reader::new(block::Block::from_slice(b"@1\nACGT\n+\n!!!!\n"));
+        assert_eq!(reader.format(), Format::Fastq);
+
+        let record = reader.next_record().unwrap().unwrap();
+        assert_eq!(record.format(), Format::Fastq);
+        assert_eq!(record.record().quality, b"!!!!");
+        assert!(reader.next_record().unwrap().is_none());
+
+        let mut reader = Reader::new(block::Block::from_slice(b">1\nACGT\n"));
+        assert_eq!(reader.format(), Format::Fasta);
+
+        let record = reader.next_record().unwrap().unwrap().into_record();
+        assert_eq!(record.sequence, b"ACGT");
+        assert!(record.quality.is_empty());
+    }
+
+    #[test]
+    fn sequential() {
+        crate::fastx_sequential!(
+            FastxCount,
+            [u64; 2],
+            |record: FastxRecord, counts: &mut [u64; 2]| {
+                match record {
+                    FastxRecord::Fasta(_) => counts[0] += 1,
+                    FastxRecord::Fastq(_) => counts[1] += 1,
+                }
+            }
+        );
+
+        let mut counts = [0; 2];
+        let mut parser = FastxCount::new();
+
+        parser
+            .with_blocksize(
+                1_000,
+                crate::tests::generate_fasta(42, 1_000, 150),
+                &mut counts,
+            )
+            .unwrap();
+        parser
+            .parse(crate::tests::generate_fastq(42, 500, 150), &mut counts)
+            .unwrap();
+
+        assert_eq!(counts, [1_000, 500]);
+    }
+
+    #[test]
+    fn sharedstate() {
+        use rayon::iter::ParallelBridge;
+        use rayon::iter::ParallelIterator;
+
+        crate::fastx_sharedstate!(
+            FastxBase,
+            std::sync::atomic::AtomicU64,
+            |record: FastxRecord, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(
+                    record.record().sequence.len() as u64,
+                    std::sync::atomic::Ordering::Relaxed,
+                );
+            }
+        );
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+        let mut parser = FastxBase::new();
+
+        parser
+            .parse(crate::tests::generate_fasta(42, 1_000, 150), &counter)
+            .unwrap();
+        parser
+            .parse(crate::tests::generate_fastq(42, 1_000, 100), &counter)
+            .unwrap();
+
+        assert_eq!(counter.into_inner(), 250_000);
+    }
+
+    #[test]
+    fn to_jsonl() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"@1 desc\nACGT\n+\n!!!!\n@2\nTG\n+\n#$\n")
+            .unwrap();
+
+        let mut output = Vec::new();
+        assert_eq!(
+            super::to_jsonl(file.path(), &mut output, jsonl::QualityEncoding::Raw).unwrap(),
+            2
+        );
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"id\":\"1\",\"seq\":\"ACGT\",\"qual\":\"!!!!\"}\n{\"id\":\"2\",\"seq\":\"TG\",\"qual\":\"#$\"}\n"
+        );
+
+        let mut output = Vec::new();
+        super::to_jsonl(
+            crate::tests::generate_fasta(42, 10, 150),
+            &mut output,
+            jsonl::QualityEncoding::Base64,
+        )
+        .unwrap();
+        assert_eq!(output.iter().filter(|c| **c == b'\n').count(), 10);
+        assert!(output.starts_with(b"{\"id\":\"0\",\"seq\":\""));
+    }
+
+    #[test]
+    fn leading_whitespace() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b" \n\t@1\nACGT\n+\n!!!!\n").unwrap();
+
+        assert_eq!(Format::detect(file.path()).unwrap(), Format::Fastq);
+    }
+
+    #[test]
+    fn unknown_format() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"Lorem ipsum dolor sit amet\n").unwrap();
+
+        assert!(matches!(
+            open(file.path()),
+            Err(error::Error::UnknownFormat)
+        ));
+
+        let empty = tempfile::NamedTempFile::new().unwrap();
+        assert!(matches!(
+            Format::detect(empty.path()),
+            Err(error::Error::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn reduce_files() {
+        let fasta = crate::tests::generate_fasta(42, 1_000, 150);
+        let fastq = crate::tests::generate_fastq(42, 500, 100);
+        let missing = fasta.path().with_extension("missing");
+        let paths = vec![
+            fasta.path().to_path_buf(),
+            missing.clone(),
+            fastq.path().to_path_buf(),
+        ];
+
+        for granularity in [Granularity::File, Granularity::Block] {
+            let ((nb_record, nb_base), errors) = reduce_files_with(
+                granularity,
+                paths.clone(),
+                || (0, 0),
+                |(nb_record, nb_base): &mut (u64, usize), record: block::Record| {
+                    *nb_record += 1;
+                    *nb_base += record.sequence.len();
+                },
+                |a, b| (a.0 + b.0, a.1 + b.1),
+            );
+
+            assert_eq!((nb_record, nb_base), (1_500, 200_000));
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].0, missing);
+            assert!(matches!(errors[0].1, error::Error::OpenFile { .. }));
+        }
+
+        let (nb_record, errors) = super::reduce_files(
+            [fastq.path()],
+            || 0,
+            |nb_record: &mut u64, _record: block::Record| *nb_record += 1,
+            |a, b| a + b,
+        );
+        assert_eq!(nb_record, 500);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "walkdir")]
+    fn walk() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+
+        for path in [
+            dir.path().join("a.fasta"),
+            dir.path().join("b.txt"),
+            dir.path().join("c.fq.gz"),
+            sub.join("d.fasta"),
+            sub.join("fasta"),
+        ] {
+            std::fs::write(path, b">1\nACGT\n").unwrap();
+        }
+
+        assert_eq!(
+            super::walk(dir.path(), &["fasta", "fq.gz"]).collect::<Vec<_>>(),
+            vec![
+                dir.path().join("a.fasta"),
+                dir.path().join("c.fq.gz"),
+                sub.join("d.fasta"),
+            ]
+        );
+        assert_eq!(super::walk(dir.path(), &[]).count(), 5);
+
+        #[cfg(unix)]
+        {
+            let other = tempfile::tempdir().unwrap();
+            std::fs::write(other.path().join("e.fasta"), b">1\nACGT\n").unwrap();
+            std::os::unix::fs::symlink(other.path(), dir.path().join("link")).unwrap();
+
+            assert_eq!(super::walk(dir.path(), &["fasta"]).count(), 2);
+            assert_eq!(walk_with(dir.path(), &["fasta"], true).count(), 3);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Write records in JSON lines format, one object `{"id":...,"seq":...,"qual":...}` per line.
+
+/* project use */
+use crate::block;
+use crate::error;
+
+/// How quality is write in JSON string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityEncoding {
+    /// Quality bytes are write as characters, bytes greater than 127 are escape as `\u00XX`
+    Raw,
+    /// Quality bytes are encode in standard base64 with padding
+    Base64,
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Write records in JSON lines format
+pub struct Writer<W>
+where
+    W: std::io::Write,
+{
+    inner: W,
+    quality: QualityEncoding,
+}
+
+impl<W> Writer<W>
+where
+    W: std::io::Write,
+{
+    pub fn new(quality: QualityEncoding, inner: W) -> Self {
+        Self { inner, quality }
+    }
+
+    /// Write record as one JSON object on one line
+    pub fn write(&mut self, record: &block::Record) -> error::Result<()> {
+        self.write_line(record)
+            .map_err(|source| error::Error::WriteFile { source })
+    }
+
+    /// Get back the underlying writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_line(&mut self, record: &block::Record) -> std::io::Result<()> {
+        self.inner.write_all(b"{\"id\":")?;
+        write_string(&mut self.inner, record.id())?;
+        self.inner.write_all(b",\"seq\":")?;
+        write_string(&mut self.inner, record.sequence)?;
+        self.inner.write_all(b",\"qual\":")?;
+        match self.quality {
+            QualityEncoding::Raw => write_string(&mut self.inner, record.quality)?,
+            QualityEncoding::Base64 => {
+                self.inner.write_all(b"\"")?;
+                self.inner.write_all(&base64(record.quality))?;
+                self.inner.write_all(b"\"")?;
+            }
+        }
+        self.inner.write_all(b"}\n")
+    }
+}
+
+/// Write bytes as a JSON string, each byte is a character, control and non-ASCII bytes are escape
+fn write_string<W>(output: &mut W, data: &[u8]) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    output.write_all(b"\"")?;
+
+    let mut begin = 0;
+    for (pos, byte) in data.iter().enumerate() {
+        if *byte >= 0x20 && *byte < 0x80 && *byte != b'"' && *byte != b'\\' {
+            continue;
+        }
+
+        output.write_all(&data[begin..pos])?;
+        match byte {
+            b'"' => output.write_all(b"\\\"")?,
+            b'\\' => output.write_all(b"\\\\")?,
+            _ => std::write!(output, "\\u{:04x}", byte)?,
+        }
+        begin = pos + 1;
+    }
+    output.write_all(&data[begin..])?;
+
+    output.write_all(b"\"")
+}
+
+/// Encode data in standard base64 with padding
+fn base64(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity((data.len() + 2) / 3 * 4);
+
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(bits >> (18 - 6 * i) & 0b11_1111) as usize]);
+            } else {
+                encoded.push(b'=');
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), b"".to_vec());
+        assert_eq!(base64(b"f"), b"Zg==".to_vec());
+        assert_eq!(base64(b"fo"), b"Zm8=".to_vec());
+        assert_eq!(base64(b"foo"), b"Zm9v".to_vec());
+        assert_eq!(base64(b"foobar"), b"Zm9vYmFy".to_vec());
+        assert_eq!(base64(&[0xff, 0x00]), b"/wA=".to_vec());
+    }
+
+    #[test]
+    fn write() {
+        let record = block::Record {
+            comment: b"@r\"1 desc",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"!\\\x7f\x80",
+        };
+
+        let mut writer = Writer::new(QualityEncoding::Raw, Vec::new());
+        writer.write(&record).unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "{\"id\":\"r\\\"1\",\"seq\":\"ACGT\",\"qual\":\"!\\\\\x7f\\u0080\"}\n"
+        );
+
+        let mut writer = Writer::new(QualityEncoding::Base64, Vec::new());
+        writer.write(&record).unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "{\"id\":\"r\\\"1\",\"seq\":\"ACGT\",\"qual\":\"IVx/gA==\"}\n"
+        );
+    }
+}
@@ -3,13 +3,37 @@
 #[macro_use]
 pub mod block;
 
+#[cfg(feature = "dedup")]
+pub mod dedup;
 pub mod error;
 pub mod fasta;
 pub mod fastq;
+pub mod fastx;
+pub mod lines;
+#[cfg(feature = "noodles")]
+pub mod noodles;
 pub mod parser;
+pub mod seq;
+pub mod stats;
+pub mod stream;
+#[cfg(feature = "zstd")]
+pub mod zstd;
 
 pub const DEFAULT_BLOCKSIZE: u64 = 65536;
 
+/// Largest blocksize choose by [default_blocksize]
+pub const MAX_BLOCKSIZE: u64 = 4 * 1024 * 1024;
+
+/// Number of blocks each thread should get in [default_blocksize]
+pub const BLOCKS_PER_THREAD: u64 = 4;
+
+/// Choose a blocksize according to file length and number of rayon threads, result is clamp between [DEFAULT_BLOCKSIZE] and [MAX_BLOCKSIZE]
+pub fn default_blocksize(file_length: u64) -> u64 {
+    let nb_block = rayon::current_num_threads() as u64 * BLOCKS_PER_THREAD;
+
+    (file_length / nb_block).clamp(DEFAULT_BLOCKSIZE, MAX_BLOCKSIZE)
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -56,4 +80,14 @@ mod tests {
 
         file
     }
+
+    #[test]
+    fn default_blocksize() {
+        let nb_block = rayon::current_num_threads() as u64 * super::BLOCKS_PER_THREAD;
+
+        assert_eq!(super::default_blocksize(0), super::DEFAULT_BLOCKSIZE);
+        assert_eq!(super::default_blocksize(1_000), super::DEFAULT_BLOCKSIZE);
+        assert_eq!(super::default_blocksize(nb_block * 1_000_000), 1_000_000);
+        assert_eq!(super::default_blocksize(u64::MAX), super::MAX_BLOCKSIZE);
+    }
 }
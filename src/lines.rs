@@ -0,0 +1,120 @@
+//! Count lines of a file as `wc -l`, file is cut in blocks at any position and blocks are read in parallel.
+
+/* crate use */
+use rayon::iter::ParallelBridge;
+use rayon::iter::ParallelIterator;
+
+/* project use */
+use crate::block;
+use crate::error;
+
+impl_producer!(
+    Producer,
+    |block: &[u8]| Ok(block.len() as u64),
+    |data: &[u8]| memchr::memchr(b'\n', data).map(|pos| pos + 1),
+    |_data: &[u8]| None
+);
+
+/// Count new line characters of file, a last line without new line isn't count, same result as `wc -l`
+pub fn count_lines<P>(path: P) -> error::Result<u64>
+where
+    P: AsRef<std::path::Path>,
+{
+    let mut producer = Producer::new(path)?;
+
+    count(&mut producer)
+}
+
+/// Same as [count_lines] with a chosen blocksize
+pub fn count_lines_with_blocksize<P>(blocksize: u64, path: P) -> error::Result<u64>
+where
+    P: AsRef<std::path::Path>,
+{
+    let mut producer = Producer::with_blocksize(blocksize, path)?;
+
+    count(&mut producer)
+}
+
+fn count(producer: &mut Producer) -> error::Result<u64> {
+    // a byte order mark is skipped by producer
+    let begin = producer.offset();
+
+    let (covered, nb_line) = producer
+        .par_bridge()
+        .map(|block| {
+            let block = block?;
+
+            Ok((
+                block.len() as u64,
+                memchr::memchr_iter(b'\n', block.data()).count() as u64,
+            ))
+        })
+        .try_reduce(|| (0, 0), |a, b| Ok((a.0 + b.0, a.1 + b.1)))?;
+
+    // producer didn't send last block if it contains only new lines
+    if begin + covered < producer.file_length() {
+        let tail = producer.block_range(begin + covered, producer.file_length())?;
+
+        Ok(nb_line + memchr::memchr_iter(b'\n', tail.data()).count() as u64)
+    } else {
+        Ok(nb_line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    fn naive(path: &std::path::Path) -> u64 {
+        std::fs::read(path)
+            .unwrap()
+            .iter()
+            .filter(|c| **c == b'\n')
+            .count() as u64
+    }
+
+    #[test]
+    fn generated() {
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+        assert_eq!(count_lines(file.path()).unwrap(), 4_000);
+        assert_eq!(count_lines_with_blocksize(100, file.path()).unwrap(), 4_000);
+    }
+
+    #[test]
+    fn trailing_lines() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..100 {
+            writeln!(file, "line {}", i).unwrap();
+        }
+        file.write_all(b"\n\n\r\n\n").unwrap();
+
+        for blocksize in [1, 7, 64, 1_000_000] {
+            assert_eq!(
+                count_lines_with_blocksize(blocksize, file.path()).unwrap(),
+                naive(file.path())
+            );
+        }
+
+        file.write_all(b"no new line").unwrap();
+        assert_eq!(count_lines_with_blocksize(7, file.path()).unwrap(), 104);
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&block::UTF8_BOM).unwrap();
+        file.write_all(b"a\nb\n\n").unwrap();
+
+        assert_eq!(count_lines_with_blocksize(2, file.path()).unwrap(), 3);
+    }
+
+    #[test]
+    fn empty() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        assert_eq!(count_lines(file.path()).unwrap(), 0);
+    }
+}
@@ -0,0 +1,74 @@
+//! Convert records in [noodles](https://github.com/zaeleus/noodles) records, only available with feature `noodles`.
+
+/* project use */
+use crate::block;
+
+impl<'a> From<&block::Record<'a>> for noodles_fasta::Record {
+    fn from(record: &block::Record<'a>) -> Self {
+        let description = match record.description() {
+            b"" => None,
+            description => Some(String::from_utf8_lossy(description).into_owned()),
+        };
+
+        noodles_fasta::Record::new(
+            noodles_fasta::record::Definition::new(
+                String::from_utf8_lossy(record.id()).into_owned(),
+                description,
+            ),
+            noodles_fasta::record::Sequence::from(record.sequence.to_vec()),
+        )
+    }
+}
+
+impl<'a> From<&block::Record<'a>> for noodles_fastq::Record {
+    fn from(record: &block::Record<'a>) -> Self {
+        let mut noodles = noodles_fastq::Record::new(
+            record.id().to_vec(),
+            record.sequence.to_vec(),
+            record.quality.to_vec(),
+        );
+        noodles
+            .description_mut()
+            .extend_from_slice(record.description());
+
+        noodles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fasta() {
+        let record = block::Record {
+            comment: b">1 desc",
+            sequence: b"ACGT",
+            plus: b"",
+            quality: b"",
+        };
+
+        let noodles = noodles_fasta::Record::from(&record);
+
+        assert_eq!(noodles.name(), "1");
+        assert_eq!(noodles.description(), Some("desc"));
+        assert_eq!(noodles.sequence().as_ref(), b"ACGT");
+    }
+
+    #[test]
+    fn fastq() {
+        let record = block::Record {
+            comment: b"@1 desc",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"!!!!",
+        };
+
+        let noodles = noodles_fastq::Record::from(&record);
+
+        assert_eq!(noodles.name(), b"1");
+        assert_eq!(noodles.description(), b"desc");
+        assert_eq!(noodles.sequence(), b"ACGT");
+        assert_eq!(noodles.quality_scores(), b"!!!!");
+    }
+}
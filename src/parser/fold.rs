@@ -0,0 +1,172 @@
+//! Each thread fold records of blocks in a local accumulator, accumulators are merged at end
+
+/* crate use */
+use rayon::iter::ParallelBridge;
+use rayon::iter::ParallelIterator;
+
+/* project use */
+use crate::block;
+use crate::error;
+
+/// Parse blocks produce by producer in parallel, records are read by read_record, each thread fold records in is own accumulator build by init, accumulators are merged by merge.
+pub fn fold<P, R, A, I, F, M>(
+    producer: P,
+    read_record: R,
+    init: I,
+    fold: F,
+    merge: M,
+) -> error::Result<A>
+where
+    P: Iterator<Item = error::Result<block::Block>> + Send,
+    R: for<'a> Fn(&'a block::Block, &mut usize) -> error::Result<Option<block::Record<'a>>>
+        + Sync
+        + Send,
+    A: Send,
+    I: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, block::Record) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    producer
+        .par_bridge()
+        .try_fold(&init, |mut accumulator, block| {
+            let block = block?;
+            let mut offset = 0;
+
+            while let Some(record) = read_record(&block, &mut offset)? {
+                fold(&mut accumulator, record);
+            }
+
+            Ok(accumulator)
+        })
+        .try_reduce(&init, |a, b| Ok(merge(a, b)))
+}
+
+/// Same as [fold] but fold get all records of a block at once, to process them in batch
+///
+/// Records of a batch borrow the same block, they are all valid together until fold return.
+pub fn fold_batch<P, R, A, I, F, M>(
+    producer: P,
+    read_record: R,
+    init: I,
+    fold: F,
+    merge: M,
+) -> error::Result<A>
+where
+    P: Iterator<Item = error::Result<block::Block>> + Send,
+    R: for<'a> Fn(&'a block::Block, &mut usize) -> error::Result<Option<block::Record<'a>>>
+        + Sync
+        + Send,
+    A: Send,
+    I: Fn() -> A + Sync + Send,
+    F: Fn(&mut A, &[block::Record]) + Sync + Send,
+    M: Fn(A, A) -> A + Sync + Send,
+{
+    producer
+        .par_bridge()
+        .try_fold(&init, |mut accumulator, block| {
+            let block = block?;
+            let mut offset = 0;
+
+            let mut records = Vec::new();
+            while let Some(record) = read_record(&block, &mut offset)? {
+                records.push(record);
+            }
+
+            fold(&mut accumulator, &records);
+
+            Ok(accumulator)
+        })
+        .try_reduce(&init, |a, b| Ok(merge(a, b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fasta;
+    use crate::fastq;
+
+    #[test]
+    fn base_count_fasta() {
+        let bases = fasta::parse_fold(
+            crate::tests::generate_fasta(42, 1_000, 150),
+            || [0u64; 4],
+            |bases: &mut [u64; 4], record: block::Record| {
+                for nuc in record.sequence {
+                    bases[(nuc >> 1 & 0b11) as usize] += 1;
+                }
+            },
+            |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]],
+        )
+        .unwrap();
+
+        assert_eq!([37378, 37548, 37548, 37526], bases);
+    }
+
+    #[test]
+    fn base_count_fastq() {
+        let bases = fold(
+            fastq::Producer::with_blocksize(1_000, crate::tests::generate_fastq(42, 1_000, 150))
+                .unwrap(),
+            fastq::Reader::read_record,
+            || [0u64; 4],
+            |bases: &mut [u64; 4], record: block::Record| {
+                for nuc in record.sequence {
+                    bases[(nuc >> 1 & 0b11) as usize] += 1;
+                }
+            },
+            |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]],
+        )
+        .unwrap();
+
+        assert_eq!([37301, 37496, 37624, 37579], bases);
+    }
+
+    #[test]
+    fn batch() {
+        let (nb_batch, nb_record, nb_base) = fold_batch(
+            fastq::Producer::with_blocksize(1_000, crate::tests::generate_fastq(42, 1_000, 150))
+                .unwrap(),
+            fastq::Reader::read_record,
+            || (0, 0, 0),
+            |(nb_batch, nb_record, nb_base): &mut (usize, usize, usize),
+             records: &[block::Record]| {
+                *nb_batch += 1;
+                *nb_record += records.len();
+                *nb_base += records.iter().map(|r| r.sequence.len()).sum::<usize>();
+            },
+            |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2),
+        )
+        .unwrap();
+
+        assert!(nb_batch > 1);
+        assert!(nb_batch < nb_record);
+        assert_eq!((nb_record, nb_base), (1_000, 150_000));
+
+        let bases = fasta::parse_fold_batch(
+            crate::tests::generate_fasta(42, 1_000, 150),
+            || 0,
+            |nb_base: &mut usize, records: &[block::Record]| {
+                *nb_base += records.iter().map(|r| r.sequence.len()).sum::<usize>()
+            },
+            |a, b| a + b,
+        )
+        .unwrap();
+        assert_eq!(bases, 150_000);
+    }
+
+    #[test]
+    fn error() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"@1\nACGT\n+\n!!!!\n@2\nAC").unwrap();
+
+        assert!(fastq::parse_fold(file.path(), || 0, |a, _| *a += 1, |a, b| a + b).is_err());
+        assert!(fastq::parse_fold_batch(
+            file.path(),
+            || 0,
+            |a, records: &[block::Record]| *a += records.len(),
+            |a, b| a + b
+        )
+        .is_err());
+    }
+}
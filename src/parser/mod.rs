@@ -1,3 +1,212 @@
 /* mod declaration */
+pub mod fold;
 pub mod sequential;
 pub mod shared_state;
+
+/* project use */
+use crate::error;
+
+/// Convert value returned by a record worker in a Result, worker can return `()` or `error::Result<()>`
+pub trait IntoResult {
+    fn into_result(self) -> error::Result<()>;
+}
+
+impl IntoResult for () {
+    #[inline(always)]
+    fn into_result(self) -> error::Result<()> {
+        Ok(())
+    }
+}
+
+impl IntoResult for error::Result<()> {
+    #[inline(always)]
+    fn into_result(self) -> error::Result<()> {
+        self
+    }
+}
+
+/// Statistics of a parsing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Number of blocks produced
+    pub blocks: u64,
+    /// Number of records send to worker
+    pub records: u64,
+    /// Number of bytes scanned
+    pub bytes: u64,
+}
+
+impl std::ops::Add for ParseStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            blocks: self.blocks + rhs.blocks,
+            records: self.records + rhs.records,
+            bytes: self.bytes + rhs.bytes,
+        }
+    }
+}
+
+impl std::ops::AddAssign for ParseStats {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+/// Information about block that contains a record, worker of [fastq_sharedstate_meta](crate::fastq_sharedstate_meta) get it with each record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockMeta {
+    /// Offset of block in file, add offset of record in block to get absolute position of record
+    pub offset: u64,
+    /// Length of block in bytes
+    pub length: u64,
+    /// Index of block in file order
+    pub block_index: u64,
+    /// Index of rayon thread that read block, 0 outside of a rayon thread pool
+    pub thread_index: usize,
+}
+
+impl BlockMeta {
+    /// Build metadata of block, block_index is set by caller
+    pub fn new(block: &crate::block::Block, block_index: u64) -> Self {
+        Self {
+            offset: block.file_offset(),
+            length: block.len() as u64,
+            block_index,
+            thread_index: rayon::current_thread_index().unwrap_or(0),
+        }
+    }
+}
+
+/// Callback call by parsers at begin or end of each block, see set_on_block_start and set_on_block_end of parsers
+pub type BlockHook = Box<dyn Fn(BlockMeta) + Send + Sync>;
+
+/// Iterator that limit number of items in flight, an item is in flight until its [Slot] is drop
+///
+/// When max items are in flight, next wait until a slot is release.
+pub struct Bounded<I> {
+    iter: I,
+    slots: std::sync::Arc<Slots>,
+}
+
+struct Slots {
+    used: std::sync::Mutex<usize>,
+    released: std::sync::Condvar,
+    max: usize,
+}
+
+/// Token of an item in flight, item is release when token is drop
+pub struct Slot {
+    slots: std::sync::Arc<Slots>,
+}
+
+impl<I> Bounded<I> {
+    /// Wrap iter, max is at least one
+    pub fn new(iter: I, max: usize) -> Self {
+        Self {
+            iter,
+            slots: std::sync::Arc::new(Slots {
+                used: std::sync::Mutex::new(0),
+                released: std::sync::Condvar::new(),
+                max: max.max(1),
+            }),
+        }
+    }
+}
+
+impl<I> Iterator for Bounded<I>
+where
+    I: Iterator,
+{
+    type Item = (I::Item, Slot);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut used = self.slots.used.lock().unwrap_or_else(|e| e.into_inner());
+        while *used >= self.slots.max {
+            used = self
+                .slots
+                .released
+                .wait(used)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+
+        let item = self.iter.next()?;
+        *used += 1;
+
+        Some((
+            item,
+            Slot {
+                slots: self.slots.clone(),
+            },
+        ))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        *self.slots.used.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+        self.slots.released.notify_one();
+    }
+}
+
+/// Range of records send to worker, skip first records and stop after limit records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseRange {
+    /// Number of records ignored at begin of file
+    pub skip: u64,
+    /// Maximal number of records send to worker, None for no limit
+    pub limit: Option<u64>,
+}
+
+impl ParseRange {
+    /// True if range contains all records
+    #[inline(always)]
+    pub fn is_full(&self) -> bool {
+        self.skip == 0 && self.limit.is_none()
+    }
+
+    /// True if record at index is in range
+    #[inline(always)]
+    pub fn contains(&self, index: u64) -> bool {
+        index >= self.skip && !self.is_after(index)
+    }
+
+    /// True if record at index is after end of range
+    #[inline(always)]
+    pub fn is_after(&self, index: u64) -> bool {
+        match self.limit {
+            Some(limit) => index >= self.skip.saturating_add(limit),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        let mut iter = Bounded::new(0..10, 2);
+
+        let (a, slot_a) = iter.next().unwrap();
+        let (b, _slot_b) = iter.next().unwrap();
+        assert_eq!((a, b), (0, 1));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let waiting = std::thread::spawn(move || {
+            let (c, _) = iter.next().unwrap();
+            sender.send(c).unwrap();
+            iter.count()
+        });
+
+        assert!(receiver
+            .recv_timeout(std::time::Duration::from_millis(50))
+            .is_err());
+
+        drop(slot_a);
+        assert_eq!(receiver.recv().unwrap(), 2);
+        assert_eq!(waiting.join().unwrap(), 7);
+    }
+}
@@ -1,14 +1,105 @@
+/// Build a sequential parser named name, producer and reader are constructors of any format
+///
+/// Format is choose when macro is invoke, same worker can be used with [fasta_sequential], [fastq_sequential] or [fastx_sequential].
 #[macro_export(local_inner_macros)]
 macro_rules! impl_sequential {
     ($name:ident, $producer:expr, $reader:expr,  $data_type:ty, $record:expr) => {
-        pub struct $name {}
+        pub struct $name {
+            cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+            range: $crate::parser::ParseRange,
+            on_block_start: Option<$crate::parser::BlockHook>,
+            on_block_end: Option<$crate::parser::BlockHook>,
+            expected_records: Option<u64>,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
 
+        #[allow(dead_code)]
         impl $name {
             pub fn new() -> Self {
-                Self {}
+                Self {
+                    cancel: None,
+                    range: $crate::parser::ParseRange::default(),
+                    on_block_start: None,
+                    on_block_end: None,
+                    expected_records: None,
+                }
+            }
+
+            /// Create a parser that stop, at next block, when cancel is set to true
+            pub fn with_cancel(cancel: std::sync::Arc<std::sync::atomic::AtomicBool>) -> Self {
+                Self {
+                    cancel: Some(cancel),
+                    range: $crate::parser::ParseRange::default(),
+                    on_block_start: None,
+                    on_block_end: None,
+                    expected_records: None,
+                }
+            }
+
+            /// Only records in range are send to worker, records are count in file order
+            ///
+            /// Skipped records are still read, parsing stop at end of range.
+            pub fn set_range(&mut self, range: $crate::parser::ParseRange) {
+                self.range = range;
+            }
+
+            /// After a parse, number of records send to worker must be expected or parse return [RecordCountMismatch]($crate::error::Error::RecordCountMismatch), None disable check
+            ///
+            /// Check is done by all parse methods, for `parse_collect_errors` mismatch is report only if no other error occur. Records out of range aren't count.
+            pub fn set_expected_records(&mut self, expected: Option<u64>) {
+                self.expected_records = expected;
+            }
+
+            fn check_expected(
+                &self,
+                stats: $crate::parser::ParseStats,
+            ) -> $crate::error::Result<$crate::parser::ParseStats> {
+                match self.expected_records {
+                    Some(expected) if expected != stats.records => {
+                        Err($crate::error::Error::RecordCountMismatch {
+                            expected,
+                            found: stats.records,
+                        })
+                    }
+                    _ => Ok(stats),
+                }
+            }
+
+            /// Call hook before first record of each block
+            pub fn set_on_block_start<F>(&mut self, hook: F)
+            where
+                F: Fn($crate::parser::BlockMeta) + Send + Sync + 'static,
+            {
+                self.on_block_start = Some(Box::new(hook));
+            }
+
+            /// Call hook after last record of each block, hook isn't call if block failed
+            pub fn set_on_block_end<F>(&mut self, hook: F)
+            where
+                F: Fn($crate::parser::BlockMeta) + Send + Sync + 'static,
+            {
+                self.on_block_end = Some(Box::new(hook));
+            }
+
+            fn check_cancel(&self) -> $crate::error::Result<()> {
+                match &self.cancel {
+                    Some(cancel) if cancel.load(std::sync::atomic::Ordering::Relaxed) => {
+                        Err($crate::error::Error::Cancelled)
+                    }
+                    _ => Ok(()),
+                }
             }
 
-            pub fn parse<P>(&mut self, path: P, data: &mut $data_type) -> $crate::error::Result<()>
+            pub fn parse<P>(
+                &mut self,
+                path: P,
+                data: &mut $data_type,
+            ) -> $crate::error::Result<$crate::parser::ParseStats>
             where
                 P: AsRef<std::path::Path>,
             {
@@ -20,40 +111,146 @@ macro_rules! impl_sequential {
                 blocksize: u64,
                 path: P,
                 data: &mut $data_type,
-            ) -> $crate::error::Result<()>
+            ) -> $crate::error::Result<$crate::parser::ParseStats>
             where
                 P: AsRef<std::path::Path>,
             {
                 let mut producer = $producer(blocksize, path)?;
+                let mut stats = $crate::parser::ParseStats::default();
+                let mut index = 0;
+                let mut block_index = 0;
 
                 while let Some(block) = producer.next_block()? {
-                    self.block(block, data)?
+                    self.check_cancel()?;
+                    stats += self.block(block, block_index, data, &mut index)?;
+                    block_index += 1;
+
+                    if self.range.is_after(index) {
+                        break;
+                    }
+                }
+
+                self.check_expected(stats)
+            }
+
+            /// Parse file without stop at first error, each block that failed is skipped and its error is collected
+            ///
+            /// An error of producer stop parsing, following blocks can't be found.
+            pub fn parse_collect_errors<P>(
+                &mut self,
+                path: P,
+                data: &mut $data_type,
+            ) -> std::result::Result<$crate::parser::ParseStats, Vec<$crate::error::Error>>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                self.collect_errors_with_blocksize($crate::DEFAULT_BLOCKSIZE, path, data)
+            }
+
+            pub fn collect_errors_with_blocksize<P>(
+                &mut self,
+                blocksize: u64,
+                path: P,
+                data: &mut $data_type,
+            ) -> std::result::Result<$crate::parser::ParseStats, Vec<$crate::error::Error>>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                let mut producer = $producer(blocksize, path).map_err(|e| std::vec![e])?;
+                let mut stats = $crate::parser::ParseStats::default();
+                let mut errors = Vec::new();
+                let mut index = 0;
+                let mut block_index = 0;
+
+                loop {
+                    let block = match producer.next_block() {
+                        Ok(Some(block)) => block,
+                        Ok(None) => break,
+                        Err(e) => {
+                            errors.push(e);
+                            break;
+                        }
+                    };
+
+                    if let Err(e) = self.check_cancel() {
+                        errors.push(e);
+                        break;
+                    }
+
+                    match self.block(block, block_index, data, &mut index) {
+                        Ok(block_stats) => stats += block_stats,
+                        Err(e) => errors.push(e),
+                    }
+                    block_index += 1;
+
+                    if self.range.is_after(index) {
+                        break;
+                    }
                 }
 
-                Ok(())
+                if errors.is_empty() {
+                    self.check_expected(stats).map_err(|e| std::vec![e])
+                } else {
+                    Err(errors)
+                }
             }
 
             fn block(
                 &mut self,
                 block: $crate::block::Block,
+                block_index: u64,
                 data: &mut $data_type,
-            ) -> $crate::error::Result<()> {
-                let mut reader = $reader(block);
+                index: &mut u64,
+            ) -> $crate::error::Result<$crate::parser::ParseStats> {
+                let meta = $crate::parser::BlockMeta::new(&block, block_index);
+                if let Some(hook) = &self.on_block_start {
+                    hook(meta);
+                }
 
-                while let Some(record) = reader.next_record()? {
-                    self.record(record, data);
+                let stats = self.records(block, data, index)?;
+
+                if let Some(hook) = &self.on_block_end {
+                    hook(meta);
                 }
 
-                Ok(())
+                Ok(stats)
             }
 
-            fn record(&self, record: $crate::block::Record, data: &mut $data_type) -> () {
-                $record(record, data);
+            fn records(
+                &mut self,
+                block: $crate::block::Block,
+                data: &mut $data_type,
+                index: &mut u64,
+            ) -> $crate::error::Result<$crate::parser::ParseStats> {
+                let mut stats = $crate::parser::ParseStats {
+                    blocks: 1,
+                    records: 0,
+                    bytes: block.len() as u64,
+                };
+                let mut reader = $reader(block);
+
+                while let Some(record) = reader.next_record()? {
+                    let current = *index;
+                    *index += 1;
+
+                    if self.range.is_after(current) {
+                        break;
+                    }
+                    if current < self.range.skip {
+                        continue;
+                    }
+
+                    $crate::parser::IntoResult::into_result($record(record, data))?;
+                    stats.records += 1;
+                }
+
+                Ok(stats)
             }
         }
     };
 }
 
+/// Build a sequential parser of fasta file
 #[macro_export(local_inner_macros)]
 macro_rules! fasta_sequential {
     ($name:ident, $data_type:ty, $record:expr) => {
@@ -67,6 +264,7 @@ macro_rules! fasta_sequential {
     };
 }
 
+/// Build a sequential parser of fastq file
 #[macro_export(local_inner_macros)]
 macro_rules! fastq_sequential {
     ($name:ident, $data_type:ty, $record:expr) => {
@@ -80,12 +278,187 @@ macro_rules! fastq_sequential {
     };
 }
 
+/// Build a sequential parser of fasta or fastq file, worker get a [FastxRecord](crate::fastx::FastxRecord)
+#[macro_export(local_inner_macros)]
+macro_rules! fastx_sequential {
+    ($name:ident, $data_type:ty, $record:expr) => {
+        impl_sequential!(
+            $name,
+            $crate::fastx::Producer::with_blocksize,
+            $crate::fastx::Reader::new,
+            $data_type,
+            $record
+        );
+    };
+}
+
+#[macro_export(local_inner_macros)]
+macro_rules! fastq_interleaved_sequential {
+    ($name:ident, $data_type:ty, $record:expr) => {
+        impl_sequential!(
+            $name,
+            $crate::fastq::interleaved::Producer::with_blocksize,
+            $crate::fastq::interleaved::Reader::new,
+            $data_type,
+            |records: ($crate::block::Record, $crate::block::Record), data: &mut $data_type| {
+                $record(records.0, records.1, data)
+            }
+        );
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use crate::block;
     use crate::fasta;
     use crate::fastq;
 
+    fn count_base(record: block::Record, counter: &mut u64) {
+        *counter += record.sequence.len() as u64;
+    }
+
+    #[test]
+    fn collect_errors() {
+        use std::io::Write;
+
+        crate::fastq_sequential!(FastqCheck, u64, |record: block::Record,
+                                                   counter: &mut u64|
+         -> crate::error::Result<()> {
+            if record.comment == b"@bad" {
+                return Err(crate::error::Error::Worker {
+                    source: "invalid record".into(),
+                });
+            }
+
+            *counter += 1;
+            Ok(())
+        });
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..100 {
+            writeln!(file, "@{}\nACGT\n+\n!!!!", i).unwrap();
+        }
+        writeln!(file, "@bad\nACGT\n+\n!!!!").unwrap();
+        for i in 0..100 {
+            writeln!(file, "@{}\nACGT\n+\n!!!!", i).unwrap();
+        }
+        writeln!(file, "@bad\nACGT\n+\n!!!!").unwrap();
+
+        let mut counter = 0;
+        let mut parser = FastqCheck::new();
+        let errors = parser
+            .collect_errors_with_blocksize(64, file.path(), &mut counter)
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|e| matches!(e, crate::error::Error::Worker { .. })));
+        assert!(counter > 190 && counter < 200);
+
+        let mut counter = 0;
+        assert_eq!(
+            parser
+                .parse_collect_errors(crate::tests::generate_fastq(42, 1_000, 150), &mut counter)
+                .unwrap()
+                .records,
+            1_000
+        );
+    }
+
+    #[test]
+    fn expected_records() {
+        crate::fastq_sequential!(FastqBase, u64, count_base);
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let mut counter = 0;
+
+        let mut parser = FastqBase::new();
+        parser.set_expected_records(Some(1_000));
+        assert!(parser.parse(file.path(), &mut counter).is_ok());
+
+        parser.set_range(crate::parser::ParseRange {
+            skip: 0,
+            limit: Some(10),
+        });
+        assert!(matches!(
+            parser.parse(file.path(), &mut counter),
+            Err(crate::error::Error::RecordCountMismatch {
+                expected: 1_000,
+                found: 10
+            })
+        ));
+        assert!(matches!(
+            parser
+                .parse_collect_errors(file.path(), &mut counter)
+                .unwrap_err()
+                .as_slice(),
+            [crate::error::Error::RecordCountMismatch {
+                expected: 1_000,
+                found: 10
+            }]
+        ));
+    }
+
+    #[test]
+    fn block_hooks() {
+        crate::fastq_sequential!(FastqBase, u64, count_base);
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let starts = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let ends = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let mut parser = FastqBase::new();
+        let sender = starts.clone();
+        parser.set_on_block_start(move |meta| sender.lock().unwrap().push(meta));
+        let sender = ends.clone();
+        parser.set_on_block_end(move |meta| sender.lock().unwrap().push(meta));
+
+        let mut counter = 0;
+        let stats = parser
+            .with_blocksize(8192, file.path(), &mut counter)
+            .unwrap();
+
+        let starts = starts.lock().unwrap();
+        assert_eq!(starts.len() as u64, stats.blocks);
+        assert_eq!(*starts, *ends.lock().unwrap());
+        assert_eq!(
+            starts.iter().map(|meta| meta.length).sum::<u64>(),
+            stats.bytes
+        );
+        assert!(starts
+            .iter()
+            .enumerate()
+            .all(|(i, meta)| meta.block_index == i as u64));
+        assert!(starts
+            .windows(2)
+            .all(|pair| pair[0].offset + pair[0].length == pair[1].offset));
+    }
+
+    #[test]
+    fn same_worker() {
+        crate::fasta_sequential!(FastaBase, u64, count_base);
+        crate::fastq_sequential!(FastqBase, u64, count_base);
+
+        let mut fasta_counter = 0;
+        FastaBase::new()
+            .parse(
+                crate::tests::generate_fasta(42, 1_000, 150),
+                &mut fasta_counter,
+            )
+            .unwrap();
+
+        let mut fastq_counter = 0;
+        FastqBase::new()
+            .parse(
+                crate::tests::generate_fastq(42, 1_000, 150),
+                &mut fastq_counter,
+            )
+            .unwrap();
+
+        assert_eq!(fasta_counter, 150_000);
+        assert_eq!(fastq_counter, 150_000);
+    }
+
     #[test]
     fn record_count_fasta() {
         impl_sequential!(
@@ -150,11 +523,19 @@ mod tests {
 
         let mut parser = FastqRecordCount::new();
 
-        parser
+        let stats = parser
             .parse(crate::tests::generate_fastq(42, 1_000, 150), &mut counter)
             .unwrap();
 
         assert_eq!(1_000, counter);
+        assert_eq!(
+            stats,
+            crate::parser::ParseStats {
+                blocks: 5,
+                records: 1_000,
+                bytes: 311_780
+            }
+        );
     }
 
     #[test]
@@ -181,4 +562,121 @@ mod tests {
 
         assert_eq!([37301, 37496, 37624, 37579], bases);
     }
+
+    #[test]
+    fn worker_error() {
+        fastq_sequential!(FastqCheckBase, u64, |record: block::Record,
+                                                counter: &mut u64|
+         -> crate::error::Result<()> {
+            if *counter == 10 {
+                return Err(crate::error::Error::Worker {
+                    source: format!("invalid base in {:?}", record.comment).into(),
+                });
+            }
+
+            *counter += 1;
+            Ok(())
+        });
+
+        let mut counter = 0;
+
+        let mut parser = FastqCheckBase::new();
+
+        assert!(matches!(
+            parser.parse(crate::tests::generate_fastq(42, 1_000, 150), &mut counter),
+            Err(crate::error::Error::Worker { .. })
+        ));
+        assert_eq!(10, counter);
+    }
+
+    #[test]
+    fn cancel() {
+        fastq_sequential!(
+            FastqRecordCount,
+            (u64, std::sync::Arc<std::sync::atomic::AtomicBool>),
+            |_record: block::Record,
+             data: &mut (u64, std::sync::Arc<std::sync::atomic::AtomicBool>)| {
+                data.0 += 1;
+                if data.0 == 10 {
+                    data.1.store(true, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+        );
+
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let mut data = (0, cancel.clone());
+
+        let mut parser = FastqRecordCount::with_cancel(cancel);
+
+        assert!(matches!(
+            parser.with_blocksize(
+                1_000,
+                crate::tests::generate_fastq(42, 1_000, 150),
+                &mut data
+            ),
+            Err(crate::error::Error::Cancelled)
+        ));
+        assert!(data.0 < 1_000);
+    }
+
+    #[test]
+    fn range() {
+        fastq_sequential!(
+            FastqIds,
+            Vec<Vec<u8>>,
+            |record: block::Record, ids: &mut Vec<Vec<u8>>| {
+                ids.push(record.id().to_vec());
+            }
+        );
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let mut ids = Vec::new();
+
+        let mut parser = FastqIds::new();
+        parser.set_range(crate::parser::ParseRange {
+            skip: 500,
+            limit: Some(3),
+        });
+
+        let stats = parser.with_blocksize(1_000, file.path(), &mut ids).unwrap();
+
+        assert_eq!(ids, vec![b"500".to_vec(), b"501".to_vec(), b"502".to_vec()]);
+        assert_eq!(stats.records, 3);
+        assert!(stats.bytes < 311_780);
+
+        ids.clear();
+        parser.set_range(crate::parser::ParseRange {
+            skip: 998,
+            limit: None,
+        });
+        parser.parse(file.path(), &mut ids).unwrap();
+
+        assert_eq!(ids, vec![b"998".to_vec(), b"999".to_vec()]);
+    }
+
+    #[test]
+    fn pair_count_interleaved() {
+        fastq_interleaved_sequential!(
+            PairCount,
+            u64,
+            |r1: block::Record, r2: block::Record, counter: &mut u64| {
+                assert_eq!(r1.comment.len(), r2.comment.len());
+                *counter += 1;
+            }
+        );
+
+        let mut counter = 0;
+
+        let mut parser = PairCount::new();
+
+        parser
+            .with_blocksize(
+                1_000,
+                crate::tests::generate_fastq(42, 1_000, 150),
+                &mut counter,
+            )
+            .unwrap();
+
+        assert_eq!(500, counter);
+    }
 }
@@ -1,20 +1,170 @@
 //! Struct that extract part of file (called block), each block is read in parallel
 
+/// Build a parallel parser named name, producer and reader are constructors of any format
+///
+/// Format is choose when macro is invoke, same worker can be used with [fasta_sharedstate], [fastq_sharedstate] or [fastx_sharedstate].
 #[macro_export(local_inner_macros)]
 macro_rules! impl_sharedstate {
     ($name:ident, $producer:expr, $reader:expr, $data_type:ty, $record:expr) => {
-        pub struct $name {}
+        impl_sharedstate!(
+            @typed $name,
+            $producer,
+            $reader,
+            $crate::block::Record,
+            $data_type,
+            $record
+        );
+    };
+    (@typed $name:ident, $producer:expr, $reader:expr, $record_type:ty, $data_type:ty, $record:expr) => {
+        impl_sharedstate!(
+            @meta $name,
+            $producer,
+            $reader,
+            $record_type,
+            $data_type,
+            |record: $record_type, data: &$data_type, _meta: $crate::parser::BlockMeta| {
+                $record(record, data)
+            }
+        );
+    };
+    (@meta $name:ident, $producer:expr, $reader:expr, $record_type:ty, $data_type:ty, $record:expr) => {
+        pub struct $name {
+            cancel: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+            range: $crate::parser::ParseRange,
+            max_in_flight: Option<usize>,
+            pool: Option<std::sync::Arc<rayon::ThreadPool>>,
+            on_block_start: Option<$crate::parser::BlockHook>,
+            on_block_end: Option<$crate::parser::BlockHook>,
+            expected_records: Option<u64>,
+        }
 
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        #[allow(dead_code)]
         impl $name {
             pub fn new() -> Self {
-                Self {}
+                Self {
+                    cancel: None,
+                    range: $crate::parser::ParseRange::default(),
+                    max_in_flight: None,
+                    pool: None,
+                    on_block_start: None,
+                    on_block_end: None,
+                    expected_records: None,
+                }
+            }
+
+            /// Create a parser that stop, before next block, when cancel is set to true
+            pub fn with_cancel(cancel: std::sync::Arc<std::sync::atomic::AtomicBool>) -> Self {
+                Self {
+                    cancel: Some(cancel),
+                    range: $crate::parser::ParseRange::default(),
+                    max_in_flight: None,
+                    pool: None,
+                    on_block_start: None,
+                    on_block_end: None,
+                    expected_records: None,
+                }
             }
 
-            pub fn parse<P>(&mut self, path: P, data: &$data_type) -> $crate::error::Result<()>
+            /// Only a range of records are send to worker, range is apply on each file
+            ///
+            /// Blocks are read in parallel so records are count in reading order not in file order, number of records send to worker is exact but which records are send isn't, use a sequential parser to get exact records.
+            pub fn set_range(&mut self, range: $crate::parser::ParseRange) {
+                self.range = range;
+            }
+
+            /// Limit number of blocks produced but not yet parsed, producer wait until a block is parsed
+            ///
+            /// Memory use by blocks is bound to roughly blocksize * max, None remove limit.
+            ///
+            /// A thread wait for a free slot while it holds the shared producer, a worker that use rayon (par_iter, join, ...) could steal this waiting job while its own block isn't release and deadlock, with a limit worker must not use rayon pool that parse.
+            pub fn set_max_in_flight_blocks(&mut self, max: Option<usize>) {
+                self.max_in_flight = max;
+            }
+
+            /// Run parsing in pool instead of rayon global pool, None use global pool
+            pub fn set_thread_pool(&mut self, pool: Option<std::sync::Arc<rayon::ThreadPool>>) {
+                self.pool = pool;
+            }
+
+            /// After a parse, number of records send to worker must be expected or parse return [RecordCountMismatch]($crate::error::Error::RecordCountMismatch), None disable check
+            ///
+            /// Check is done by all parse methods, for `parse_collect_errors` mismatch is report only if no other error occur and for `parse_files` each file is check. Records out of range aren't count.
+            pub fn set_expected_records(&mut self, expected: Option<u64>) {
+                self.expected_records = expected;
+            }
+
+            fn check_expected(
+                &self,
+                stats: $crate::parser::ParseStats,
+            ) -> $crate::error::Result<$crate::parser::ParseStats> {
+                match self.expected_records {
+                    Some(expected) if expected != stats.records => {
+                        Err($crate::error::Error::RecordCountMismatch {
+                            expected,
+                            found: stats.records,
+                        })
+                    }
+                    _ => Ok(stats),
+                }
+            }
+
+            /// Call hook before first record of each block, hook is call by thread that read block
+            pub fn set_on_block_start<F>(&mut self, hook: F)
+            where
+                F: Fn($crate::parser::BlockMeta) + Send + Sync + 'static,
+            {
+                self.on_block_start = Some(Box::new(hook));
+            }
+
+            /// Call hook after last record of each block, hook is call by thread that read block and isn't call if block failed
+            pub fn set_on_block_end<F>(&mut self, hook: F)
+            where
+                F: Fn($crate::parser::BlockMeta) + Send + Sync + 'static,
+            {
+                self.on_block_end = Some(Box::new(hook));
+            }
+
+            /// Run op in thread pool of parser
+            fn install<OP, R>(&self, op: OP) -> R
+            where
+                OP: FnOnce() -> R + Send,
+                R: Send,
+            {
+                match &self.pool {
+                    Some(pool) => pool.install(op),
+                    None => op(),
+                }
+            }
+
+            fn check_cancel(&self) -> $crate::error::Result<()> {
+                match &self.cancel {
+                    Some(cancel) if cancel.load(std::sync::atomic::Ordering::Relaxed) => {
+                        Err($crate::error::Error::Cancelled)
+                    }
+                    _ => Ok(()),
+                }
+            }
+
+            pub fn parse<P>(
+                &mut self,
+                path: P,
+                data: &$data_type,
+            ) -> $crate::error::Result<$crate::parser::ParseStats>
             where
                 P: AsRef<std::path::Path>,
             {
-                self.with_blocksize($crate::DEFAULT_BLOCKSIZE, path, data)
+                // if metadata can't be read producer will report error
+                let blocksize = std::fs::metadata(path.as_ref())
+                    .map(|metadata| $crate::default_blocksize(metadata.len()))
+                    .unwrap_or($crate::DEFAULT_BLOCKSIZE);
+
+                self.with_blocksize(blocksize, path, data)
             }
 
             fn with_blocksize<P>(
@@ -22,31 +172,288 @@ macro_rules! impl_sharedstate {
                 blocksize: u64,
                 path: P,
                 data: &$data_type,
-            ) -> $crate::error::Result<()>
+            ) -> $crate::error::Result<$crate::parser::ParseStats>
             where
                 P: AsRef<std::path::Path>,
             {
                 let producer = $producer(blocksize, path)?;
+                let index = std::sync::atomic::AtomicU64::new(0);
 
-                match producer
+                // blocks are number before bridge, so index follow file order
+                let stats = self.install(|| {
+                    $crate::parser::Bounded::new(
+                        producer.enumerate(),
+                        self.max_in_flight.unwrap_or(usize::MAX),
+                    )
                     .par_bridge()
-                    .map(|block| {
-                        let mut reader = $reader(block?);
-                        while let Some(record) = reader.next_record()? {
-                            $record(record, data);
-                        }
-                        Ok(())
+                    .map(|((block_index, block), _slot)| {
+                        let block = block?;
+                        let meta = $crate::parser::BlockMeta::new(&block, block_index as u64);
+
+                        self.block(block, meta, &index, |record| $record(record, data, meta))
                     })
-                    .find_any(|x| x.is_err())
+                    .try_reduce($crate::parser::ParseStats::default, |a, b| Ok(a + b))
+                })?;
+
+                self.check_expected(stats)
+            }
+
+            /// Parse file without stop at first error, each block that failed is skipped and its error is collected
+            ///
+            /// Errors are in reading order not in file order. An error of producer stop parsing, following blocks can't be found.
+            pub fn parse_collect_errors<P>(
+                &self,
+                path: P,
+                data: &$data_type,
+            ) -> std::result::Result<$crate::parser::ParseStats, Vec<$crate::error::Error>>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                let blocksize = std::fs::metadata(path.as_ref())
+                    .map(|metadata| $crate::default_blocksize(metadata.len()))
+                    .unwrap_or($crate::DEFAULT_BLOCKSIZE);
+
+                self.collect_errors_with_blocksize(blocksize, path, data)
+            }
+
+            fn collect_errors_with_blocksize<P>(
+                &self,
+                blocksize: u64,
+                path: P,
+                data: &$data_type,
+            ) -> std::result::Result<$crate::parser::ParseStats, Vec<$crate::error::Error>>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                let producer = $producer(blocksize, path).map_err(|e| std::vec![e])?;
+                let index = std::sync::atomic::AtomicU64::new(0);
+
+                // producer can't go further after an error
+                let blocks = producer.enumerate().scan(false, |failed, (block_index, block)| {
+                    if *failed {
+                        return None;
+                    }
+                    *failed = block.is_err();
+
+                    Some((block_index, block))
+                });
+
+                let results: Vec<$crate::error::Result<$crate::parser::ParseStats>> = self.install(|| {
+                    $crate::parser::Bounded::new(blocks, self.max_in_flight.unwrap_or(usize::MAX))
+                        .par_bridge()
+                        .map(|((block_index, block), _slot)| {
+                            let block = block?;
+                            let meta = $crate::parser::BlockMeta::new(&block, block_index as u64);
+
+                            self.block(block, meta, &index, |record| $record(record, data, meta))
+                        })
+                        .collect()
+                });
+
+                let mut stats = $crate::parser::ParseStats::default();
+                let mut errors = Vec::new();
+                for result in results {
+                    match result {
+                        Ok(block_stats) => stats += block_stats,
+                        Err(e) => errors.push(e),
+                    }
+                }
+
+                if errors.is_empty() {
+                    self.check_expected(stats).map_err(|e| std::vec![e])
+                } else {
+                    Err(errors)
+                }
+            }
+
+            /// Parse file in parallel, each worker thread build its own local data with init, local data are merged by reduce at end
+            ///
+            /// Worker get a mutable reference on local data of its thread so no lock or atomic are needed.
+            pub fn parse_local<P, L, I, W, T, M>(
+                &self,
+                path: P,
+                init: I,
+                worker: W,
+                reduce: M,
+            ) -> $crate::error::Result<L>
+            where
+                P: AsRef<std::path::Path>,
+                L: Send,
+                I: Fn() -> L + Sync + Send,
+                W: Fn($record_type, &mut L) -> T + Sync + Send,
+                T: $crate::parser::IntoResult,
+                M: Fn(L, L) -> L + Sync + Send,
+            {
+                let blocksize = std::fs::metadata(path.as_ref())
+                    .map(|metadata| $crate::default_blocksize(metadata.len()))
+                    .unwrap_or($crate::DEFAULT_BLOCKSIZE);
+
+                let producer = $producer(blocksize, path)?;
+                let index = std::sync::atomic::AtomicU64::new(0);
+
+                let (local, stats) = self.install(|| {
+                    $crate::parser::Bounded::new(
+                        producer.enumerate(),
+                        self.max_in_flight.unwrap_or(usize::MAX),
+                    )
+                    .par_bridge()
+                    .try_fold(
+                        || (init(), $crate::parser::ParseStats::default()),
+                        |(mut local, stats), ((block_index, block), _slot)| {
+                            let block = block?;
+                            let meta = $crate::parser::BlockMeta::new(&block, block_index as u64);
+
+                            let block_stats =
+                                self.block(block, meta, &index, |record| worker(record, &mut local))?;
+                            Ok((local, stats + block_stats))
+                        },
+                    )
+                    .try_reduce(
+                        || (init(), $crate::parser::ParseStats::default()),
+                        |a, b| Ok((reduce(a.0, b.0), a.1 + b.1)),
+                    )
+                })?;
+
+                self.check_expected(stats)?;
+
+                Ok(local)
+            }
+
+            /// Parse many files in parallel, each file is read sequentially by one thread.
+            ///
+            /// Parsing didn't stop at first error, path of each file that failed are returned with error.
+            pub fn parse_files<P, I>(
+                &self,
+                paths: I,
+                data: &$data_type,
+            ) -> Vec<(std::path::PathBuf, $crate::error::Error)>
+            where
+                P: AsRef<std::path::Path> + Send,
+                I: rayon::iter::IntoParallelIterator<Item = P> + Send,
+            {
+                self.files_with_blocksize($crate::DEFAULT_BLOCKSIZE, paths, data)
+            }
+
+            pub fn files_with_blocksize<P, I>(
+                &self,
+                blocksize: u64,
+                paths: I,
+                data: &$data_type,
+            ) -> Vec<(std::path::PathBuf, $crate::error::Error)>
+            where
+                P: AsRef<std::path::Path> + Send,
+                I: rayon::iter::IntoParallelIterator<Item = P> + Send,
+            {
+                self.install(|| {
+                    rayon::iter::ParallelIterator::collect(rayon::iter::ParallelIterator::filter_map(
+                        rayon::iter::IntoParallelIterator::into_par_iter(paths),
+                        |path| match self.file(blocksize, &path, data) {
+                            Ok(_) => None,
+                            Err(e) => Some((path.as_ref().to_path_buf(), e)),
+                        },
+                    ))
+                })
+            }
+
+            fn file<P>(
+                &self,
+                blocksize: u64,
+                path: P,
+                data: &$data_type,
+            ) -> $crate::error::Result<$crate::parser::ParseStats>
+            where
+                P: AsRef<std::path::Path>,
+            {
+                let mut producer = $producer(blocksize, path)?;
+                let mut stats = $crate::parser::ParseStats::default();
+                let index = std::sync::atomic::AtomicU64::new(0);
+
+                let mut block_index = 0;
+                while let Some(block) = producer.next_block()? {
+                    let meta = $crate::parser::BlockMeta::new(&block, block_index);
+                    block_index += 1;
+
+                    stats += self.block(block, meta, &index, |record| $record(record, data, meta))?;
+                }
+
+                self.check_expected(stats)
+            }
+
+            fn block<F, T>(
+                &self,
+                block: $crate::block::Block,
+                meta: $crate::parser::BlockMeta,
+                index: &std::sync::atomic::AtomicU64,
+                worker: F,
+            ) -> $crate::error::Result<$crate::parser::ParseStats>
+            where
+                F: FnMut($record_type) -> T,
+                T: $crate::parser::IntoResult,
+            {
+                self.check_cancel()?;
+
+                if let Some(hook) = &self.on_block_start {
+                    hook(meta);
+                }
+
+                let stats = self.records(block, index, worker)?;
+
+                if let Some(hook) = &self.on_block_end {
+                    hook(meta);
+                }
+
+                Ok(stats)
+            }
+
+            fn records<F, T>(
+                &self,
+                block: $crate::block::Block,
+                index: &std::sync::atomic::AtomicU64,
+                mut worker: F,
+            ) -> $crate::error::Result<$crate::parser::ParseStats>
+            where
+                F: FnMut($record_type) -> T,
+                T: $crate::parser::IntoResult,
+            {
+
+                let mut stats = $crate::parser::ParseStats {
+                    blocks: 1,
+                    records: 0,
+                    bytes: block.len() as u64,
+                };
+
+                // range is over, block is ignored
+                if self
+                    .range
+                    .is_after(index.load(std::sync::atomic::Ordering::Relaxed))
                 {
-                    Some(e) => e,
-                    None => Ok(()),
+                    return Ok(stats);
                 }
+
+                let mut reader = $reader(block);
+                while let Some(record) = reader.next_record()? {
+                    // index is only update if range is used
+                    if !self.range.is_full() {
+                        let current = index.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        if self.range.is_after(current) {
+                            break;
+                        }
+                        if current < self.range.skip {
+                            continue;
+                        }
+                    }
+
+                    $crate::parser::IntoResult::into_result(worker(record))?;
+                    stats.records += 1;
+                }
+
+                Ok(stats)
             }
         }
     };
 }
 
+/// Build a parallel parser of fasta file
 #[macro_export(local_inner_macros)]
 macro_rules! fasta_sharedstate {
     ($name:ident, $data_type:ty, $record:expr) => {
@@ -60,6 +467,7 @@ macro_rules! fasta_sharedstate {
     };
 }
 
+/// Build a parallel parser of fastq file
 #[macro_export(local_inner_macros)]
 macro_rules! fastq_sharedstate {
     ($name:ident, $data_type:ty, $record:expr) => {
@@ -73,6 +481,51 @@ macro_rules! fastq_sharedstate {
     };
 }
 
+/// Build a parallel parser of fasta or fastq file, worker get a [FastxRecord](crate::fastx::FastxRecord)
+#[macro_export(local_inner_macros)]
+macro_rules! fastx_sharedstate {
+    ($name:ident, $data_type:ty, $record:expr) => {
+        impl_sharedstate!(
+            @typed $name,
+            $crate::fastx::Producer::with_blocksize,
+            $crate::fastx::Reader::new,
+            $crate::fastx::FastxRecord,
+            $data_type,
+            $record
+        );
+    };
+}
+
+/// Same as [fasta_sharedstate] but worker also get [BlockMeta](crate::parser::BlockMeta) of block that contains record
+#[macro_export(local_inner_macros)]
+macro_rules! fasta_sharedstate_meta {
+    ($name:ident, $data_type:ty, $record:expr) => {
+        impl_sharedstate!(
+            @meta $name,
+            $crate::fasta::Producer::with_blocksize,
+            $crate::fasta::Reader::new,
+            $crate::block::Record,
+            $data_type,
+            $record
+        );
+    };
+}
+
+/// Same as [fastq_sharedstate] but worker also get [BlockMeta](crate::parser::BlockMeta) of block that contains record
+#[macro_export(local_inner_macros)]
+macro_rules! fastq_sharedstate_meta {
+    ($name:ident, $data_type:ty, $record:expr) => {
+        impl_sharedstate!(
+            @meta $name,
+            $crate::fastq::Producer::with_blocksize,
+            $crate::fastq::Reader::new,
+            $crate::block::Record,
+            $data_type,
+            $record
+        );
+    };
+}
+
 #[cfg(test)]
 mod tests {
     /* crate use */
@@ -148,11 +601,19 @@ mod tests {
 
         let mut parser = FastqRecordCount::new();
 
-        parser
+        let stats = parser
             .parse(crate::tests::generate_fastq(42, 1_000, 150), &counter)
             .unwrap();
 
         assert_eq!(1000, counter.into_inner());
+        assert_eq!(
+            stats,
+            crate::parser::ParseStats {
+                blocks: 5,
+                records: 1_000,
+                bytes: 311_780
+            }
+        );
     }
 
     #[test]
@@ -185,4 +646,419 @@ mod tests {
             std::mem::transmute::<[std::sync::atomic::AtomicU64; 4], [u64; 4]>(counter)
         });
     }
+
+    #[test]
+    fn base_count_local() {
+        fastq_sharedstate!(FastqLocal, (), |_record: block::Record, _data: &()| {});
+
+        let parser = FastqLocal::new();
+
+        let bases = parser
+            .parse_local(
+                crate::tests::generate_fastq(42, 1_000, 150),
+                || [0u64; 4],
+                |record: block::Record, bases: &mut [u64; 4]| {
+                    for nuc in record.sequence {
+                        bases[(nuc >> 1 & 0b11) as usize] += 1;
+                    }
+                },
+                |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]],
+            )
+            .unwrap();
+
+        assert_eq!([37301, 37496, 37624, 37579], bases);
+    }
+
+    #[test]
+    fn block_meta() {
+        fastq_sharedstate_meta!(
+            FastqMeta,
+            std::sync::Mutex<Vec<(u64, u64)>>,
+            |_record: block::Record,
+             blocks: &std::sync::Mutex<Vec<(u64, u64)>>,
+             meta: crate::parser::BlockMeta| {
+                assert!(meta.thread_index < rayon::current_num_threads());
+                blocks.lock().unwrap().push((meta.block_index, meta.offset));
+            }
+        );
+
+        let blocks = std::sync::Mutex::new(Vec::new());
+
+        let parser = FastqMeta::new();
+
+        parser
+            .with_blocksize(
+                50_000,
+                crate::tests::generate_fastq(42, 1_000, 150),
+                &blocks,
+            )
+            .unwrap();
+
+        let mut blocks = blocks.into_inner().unwrap();
+        blocks.sort_unstable();
+        blocks.dedup();
+
+        assert_eq!(blocks.len(), 7);
+        assert_eq!(blocks[0], (0, 0));
+        for (index, window) in blocks.windows(2).enumerate() {
+            assert_eq!(window[1].0, index as u64 + 1);
+            assert!(window[0].1 < window[1].1 && window[1].1 - window[0].1 <= 50_000);
+        }
+    }
+
+    #[test]
+    fn worker_error() {
+        fasta_sharedstate!(
+            FastaCheckBase,
+            std::sync::atomic::AtomicU64,
+            |record: block::Record,
+             counter: &std::sync::atomic::AtomicU64|
+             -> crate::error::Result<()> {
+                if record.comment == b">500" {
+                    return Err(crate::error::Error::Worker {
+                        source: "invalid record".into(),
+                    });
+                }
+
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            }
+        );
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+
+        let mut parser = FastaCheckBase::new();
+
+        assert!(matches!(
+            parser.parse(crate::tests::generate_fasta(42, 1_000, 150), &counter),
+            Err(crate::error::Error::Worker { .. })
+        ));
+        assert!(counter.into_inner() < 1000);
+    }
+
+    #[test]
+    fn expected_records() {
+        fastq_sharedstate!(
+            FastqRecordCount,
+            std::sync::atomic::AtomicU64,
+            |_record: block::Record, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let counter = std::sync::atomic::AtomicU64::new(0);
+
+        let mut parser = FastqRecordCount::new();
+        parser.set_expected_records(Some(1_000));
+        assert_eq!(parser.parse(file.path(), &counter).unwrap().records, 1_000);
+
+        parser.set_expected_records(Some(999));
+        assert!(matches!(
+            parser.parse(file.path(), &counter),
+            Err(crate::error::Error::RecordCountMismatch {
+                expected: 999,
+                found: 1_000
+            })
+        ));
+
+        assert!(matches!(
+            parser.parse_local(
+                file.path(),
+                || 0,
+                |_record, local: &mut u64| *local += 1,
+                |a, b| a + b
+            ),
+            Err(crate::error::Error::RecordCountMismatch {
+                expected: 999,
+                found: 1_000
+            })
+        ));
+        assert!(matches!(
+            parser
+                .parse_collect_errors(file.path(), &counter)
+                .unwrap_err()
+                .as_slice(),
+            [crate::error::Error::RecordCountMismatch {
+                expected: 999,
+                found: 1_000
+            }]
+        ));
+        let errors = parser.parse_files(std::vec![file.path(), file.path()], &counter);
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|(_, e)| matches!(e, crate::error::Error::RecordCountMismatch { .. })));
+
+        parser.set_expected_records(Some(1_000));
+        assert_eq!(
+            parser
+                .parse_local(
+                    file.path(),
+                    || 0,
+                    |_record, local: &mut u64| *local += 1,
+                    |a, b| a + b
+                )
+                .unwrap(),
+            1_000
+        );
+        assert!(parser
+            .parse_files(std::vec![file.path()], &counter)
+            .is_empty());
+
+        parser.set_expected_records(None);
+        assert!(parser.parse(file.path(), &counter).is_ok());
+    }
+
+    #[test]
+    fn block_hooks() {
+        fastq_sharedstate!(
+            FastqRecordCount,
+            std::sync::atomic::AtomicU64,
+            |_record: block::Record, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let length = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let ends = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+
+        let mut parser = FastqRecordCount::new();
+        let sender = length.clone();
+        parser.set_on_block_start(move |meta| {
+            sender.fetch_add(meta.length, std::sync::atomic::Ordering::SeqCst);
+        });
+        let sender = ends.clone();
+        parser.set_on_block_end(move |_meta| {
+            sender.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        });
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+        let stats = parser.with_blocksize(8192, file.path(), &counter).unwrap();
+
+        assert_eq!(
+            length.load(std::sync::atomic::Ordering::SeqCst),
+            std::fs::metadata(file.path()).unwrap().len()
+        );
+        assert_eq!(ends.load(std::sync::atomic::Ordering::SeqCst), stats.blocks);
+        assert!(stats.blocks > 1);
+    }
+
+    #[test]
+    fn collect_errors() {
+        use std::io::Write;
+
+        fastq_sharedstate!(
+            FastqCheck,
+            std::sync::atomic::AtomicU64,
+            |record: block::Record,
+             counter: &std::sync::atomic::AtomicU64|
+             -> crate::error::Result<()> {
+                if record.comment == b"@bad" {
+                    return Err(crate::error::Error::Worker {
+                        source: "invalid record".into(),
+                    });
+                }
+
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            }
+        );
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for _ in 0..3 {
+            for i in 0..100 {
+                writeln!(file, "@{}\nACGT\n+\n!!!!", i).unwrap();
+            }
+            writeln!(file, "@bad\nACGT\n+\n!!!!").unwrap();
+        }
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+        let parser = FastqCheck::new();
+        let errors = parser
+            .collect_errors_with_blocksize(64, file.path(), &counter)
+            .unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(counter.into_inner() > 280);
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+        assert_eq!(
+            parser
+                .parse_collect_errors(crate::tests::generate_fastq(42, 1_000, 150), &counter)
+                .unwrap()
+                .records,
+            1_000
+        );
+        assert_eq!(counter.into_inner(), 1_000);
+    }
+
+    #[test]
+    fn max_in_flight() {
+        fastq_sharedstate!(
+            FastqRecordCount,
+            std::sync::atomic::AtomicU64,
+            |_record: block::Record, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+        let pool = std::sync::Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(4)
+                .build()
+                .unwrap(),
+        );
+
+        for max in [1, 2] {
+            let counter = std::sync::atomic::AtomicU64::new(0);
+            let in_flight = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let high_water = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+            let mut parser = FastqRecordCount::new();
+            parser.set_thread_pool(Some(pool.clone()));
+            parser.set_max_in_flight_blocks(Some(max));
+
+            let (current, high) = (in_flight.clone(), high_water.clone());
+            parser.set_on_block_start(move |_meta| {
+                let now = current.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                high.fetch_max(now, std::sync::atomic::Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_micros(200));
+            });
+            let current = in_flight.clone();
+            parser.set_on_block_end(move |_meta| {
+                current.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+            });
+
+            parser.with_blocksize(1_000, file.path(), &counter).unwrap();
+
+            assert_eq!(1_000, counter.into_inner());
+            let high = high_water.load(std::sync::atomic::Ordering::SeqCst);
+            assert!(
+                high >= 1 && high <= max,
+                "{} blocks in flight, max {}",
+                high,
+                max
+            );
+        }
+    }
+
+    #[test]
+    fn thread_pool() {
+        fastq_sharedstate!(
+            FastqThreads,
+            std::sync::atomic::AtomicU64,
+            |_record: block::Record, threads: &std::sync::atomic::AtomicU64| {
+                threads.fetch_max(
+                    rayon::current_num_threads() as u64,
+                    std::sync::atomic::Ordering::SeqCst,
+                );
+            }
+        );
+
+        let threads = std::sync::atomic::AtomicU64::new(0);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(3)
+            .build()
+            .unwrap();
+
+        let mut parser = FastqThreads::new();
+        parser.set_thread_pool(Some(std::sync::Arc::new(pool)));
+
+        parser
+            .parse(crate::tests::generate_fastq(42, 1_000, 150), &threads)
+            .unwrap();
+
+        assert_eq!(3, threads.into_inner());
+    }
+
+    #[test]
+    fn cancel() {
+        fastq_sharedstate!(
+            FastqRecordCount,
+            std::sync::atomic::AtomicU64,
+            |_record: block::Record, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+        let cancel = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+        let mut parser = FastqRecordCount::with_cancel(cancel.clone());
+
+        assert!(matches!(
+            parser.parse(crate::tests::generate_fastq(42, 1_000, 150), &counter),
+            Err(crate::error::Error::Cancelled)
+        ));
+        assert_eq!(0, counter.load(std::sync::atomic::Ordering::SeqCst));
+
+        cancel.store(false, std::sync::atomic::Ordering::Relaxed);
+        parser
+            .parse(crate::tests::generate_fastq(42, 1_000, 150), &counter)
+            .unwrap();
+        assert_eq!(1000, counter.into_inner());
+    }
+
+    #[test]
+    fn range() {
+        fastq_sharedstate!(
+            FastqRecordCount,
+            std::sync::atomic::AtomicU64,
+            |_record: block::Record, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+
+        let mut parser = FastqRecordCount::new();
+        parser.set_range(crate::parser::ParseRange {
+            skip: 100,
+            limit: Some(250),
+        });
+
+        let stats = parser
+            .parse(crate::tests::generate_fastq(42, 1_000, 150), &counter)
+            .unwrap();
+
+        assert_eq!(250, counter.into_inner());
+        assert_eq!(stats.records, 250);
+    }
+
+    #[test]
+    fn record_count_files() {
+        fasta_sharedstate!(
+            FastaRecordCount,
+            std::sync::atomic::AtomicU64,
+            |_record: block::Record, counter: &std::sync::atomic::AtomicU64| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        );
+
+        let files = (0..10)
+            .map(|i| crate::tests::generate_fasta(i, 100, 150))
+            .collect::<Vec<tempfile::NamedTempFile>>();
+
+        let mut paths = files
+            .iter()
+            .map(|file| file.path().to_path_buf())
+            .collect::<Vec<std::path::PathBuf>>();
+        paths.push(std::path::PathBuf::from("this/file/not/exist.fasta"));
+
+        let counter = std::sync::atomic::AtomicU64::new(0);
+
+        let parser = FastaRecordCount::new();
+
+        let errors = parser.parse_files(paths, &counter);
+
+        assert_eq!(1000, counter.into_inner());
+        assert_eq!(1, errors.len());
+        assert_eq!(
+            std::path::PathBuf::from("this/file/not/exist.fasta"),
+            errors[0].0
+        );
+        assert!(matches!(errors[0].1, crate::error::Error::OpenFile { .. }));
+    }
 }
@@ -0,0 +1,274 @@
+//! Function to manipulate sequence of a record.
+
+/* crate use */
+
+/* project use */
+
+/// Complement of each byte, IUPAC ambiguity codes are complemented, case is keep, other bytes are unchanged
+const COMPLEMENT: [u8; 256] = {
+    let mut table = [0; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        table[i] = i as u8;
+        i += 1;
+    }
+
+    let pairs = [
+        (b'A', b'T'),
+        (b'C', b'G'),
+        (b'R', b'Y'),
+        (b'K', b'M'),
+        (b'B', b'V'),
+        (b'D', b'H'),
+    ];
+
+    let mut i = 0;
+    while i < pairs.len() {
+        let (a, b) = pairs[i];
+        table[a as usize] = b;
+        table[b as usize] = a;
+        table[a.to_ascii_lowercase() as usize] = b.to_ascii_lowercase();
+        table[b.to_ascii_lowercase() as usize] = a.to_ascii_lowercase();
+        i += 1;
+    }
+
+    table
+};
+
+/// Get reverse complement of sequence
+pub fn revcomp(sequence: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(sequence.len());
+
+    revcomp_into(sequence, &mut out);
+
+    out
+}
+
+/// Write reverse complement of sequence in out, previous content of out is erased but its allocation is reused
+pub fn revcomp_into(sequence: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+    out.extend(sequence.iter().rev().map(|nuc| COMPLEMENT[*nuc as usize]));
+}
+
+/// Unpack the first length bases of a sequence packed by [Record::pack_2bit](crate::block::Record::pack_2bit), bases are uppercase
+pub fn unpack_2bit(packed: &[u8], length: usize) -> Vec<u8> {
+    (0..length.min(packed.len() * 4))
+        .map(|i| b"ACTG"[(packed[i / 4] >> (6 - 2 * (i % 4)) & 0b11) as usize])
+        .collect()
+}
+
+/// Index of each nucleotide in a codon table, T or U: 0, C: 1, A: 2, G: 3, other: 4
+const CODON_INDEX: [u8; 256] = {
+    let mut table = [4; 256];
+
+    table[b'T' as usize] = 0;
+    table[b't' as usize] = 0;
+    table[b'U' as usize] = 0;
+    table[b'u' as usize] = 0;
+    table[b'C' as usize] = 1;
+    table[b'c' as usize] = 1;
+    table[b'A' as usize] = 2;
+    table[b'a' as usize] = 2;
+    table[b'G' as usize] = 3;
+    table[b'g' as usize] = 3;
+
+    table
+};
+
+/// Genetic code, amino acid of each codon, stop codon is '*'
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodonTable {
+    /// Amino acids of codons in NCBI order, nucleotides are sort T, C, A, G
+    amino_acids: [u8; 64],
+}
+
+impl CodonTable {
+    /// Standard code, NCBI table 1
+    pub const STANDARD: Self =
+        Self::new(*b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG");
+
+    /// Vertebrate mitochondrial code, NCBI table 2
+    pub const VERTEBRATE_MITOCHONDRIAL: Self =
+        Self::new(*b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG");
+
+    /// Build a table from amino acids of the 64 codons in NCBI order (TTT, TTC, TTA, TTG, TCT, ...)
+    pub const fn new(amino_acids: [u8; 64]) -> Self {
+        Self { amino_acids }
+    }
+
+    /// Get amino acid of codon, codon with a base other than A, C, G, T or U is 'X'
+    #[inline(always)]
+    pub fn amino_acid(&self, codon: &[u8]) -> u8 {
+        let mut index = 0;
+        for nuc in codon.iter().take(3) {
+            match CODON_INDEX[*nuc as usize] {
+                4 => return b'X',
+                value => index = index * 4 + value as usize,
+            }
+        }
+
+        self.amino_acids[index]
+    }
+}
+
+impl Default for CodonTable {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// Translate sequence with standard codon table, frame is the number of bases skipped before first codon
+///
+/// An incomplete codon at end of sequence is dropped, an ambiguous codon is translated in 'X'.
+pub fn translate(sequence: &[u8], frame: u8) -> Vec<u8> {
+    translate_with(&CodonTable::STANDARD, sequence, frame)
+}
+
+/// Translate sequence with table, see [translate]
+pub fn translate_with(table: &CodonTable, sequence: &[u8], frame: u8) -> Vec<u8> {
+    sequence
+        .get(frame as usize..)
+        .unwrap_or(&[])
+        .chunks_exact(3)
+        .map(|codon| table.amino_acid(codon))
+        .collect()
+}
+
+/// Translate sequence in its three forward frames then the three frames of its reverse complement, with standard codon table
+pub fn translate_six_frames(sequence: &[u8]) -> [Vec<u8>; 6] {
+    let reverse = revcomp(sequence);
+
+    [
+        translate(sequence, 0),
+        translate(sequence, 1),
+        translate(sequence, 2),
+        translate(&reverse, 0),
+        translate(&reverse, 1),
+        translate(&reverse, 2),
+    ]
+}
+
+/// Collapse each run of identical bases in a single base, comparison is case sensitive
+pub fn homopolymer_compress(sequence: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(sequence.len());
+
+    homopolymer_compress_into(sequence, &mut out);
+
+    out
+}
+
+/// Write homopolymer compression of sequence in out, previous content of out is erased but its allocation is reused
+pub fn homopolymer_compress_into(sequence: &[u8], out: &mut Vec<u8>) {
+    out.clear();
+
+    for nuc in sequence {
+        if out.last() != Some(nuc) {
+            out.push(*nuc);
+        }
+    }
+}
+
+/// Collapse each run of identical bases in a single base and get length of each run
+pub fn homopolymer_compress_with_lengths(sequence: &[u8]) -> (Vec<u8>, Vec<u32>) {
+    let mut bases = Vec::with_capacity(sequence.len());
+    let mut lengths: Vec<u32> = Vec::with_capacity(sequence.len());
+
+    for nuc in sequence {
+        match (bases.last(), lengths.last_mut()) {
+            (Some(last), Some(length)) if last == nuc => *length += 1,
+            _ => {
+                bases.push(*nuc);
+                lengths.push(1);
+            }
+        }
+    }
+
+    (bases, lengths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revcomp_dna() {
+        assert_eq!(revcomp(b"ACGT"), b"ACGT".to_vec());
+        assert_eq!(revcomp(b"AACCGGTTA"), b"TAACCGGTT".to_vec());
+        assert_eq!(revcomp(b"acgtN"), b"Nacgt".to_vec());
+        assert_eq!(revcomp(b""), b"".to_vec());
+    }
+
+    #[test]
+    fn revcomp_ambiguity() {
+        assert_eq!(revcomp(b"RYKMBVDHSWN"), b"NWSDHBVKMRY".to_vec());
+        assert_eq!(revcomp(b"rykm"), b"kmry".to_vec());
+    }
+
+    #[test]
+    fn revcomp_into_reuse() {
+        let mut out = Vec::with_capacity(16);
+
+        revcomp_into(b"AAAAAAAAAA", &mut out);
+        assert_eq!(out, b"TTTTTTTTTT".to_vec());
+
+        revcomp_into(b"GC", &mut out);
+        assert_eq!(out, b"GC".to_vec());
+        assert!(out.capacity() >= 16);
+    }
+
+    #[test]
+    fn translate_frames() {
+        assert_eq!(translate(b"ATGGCCTAA", 0), b"MA*".to_vec());
+        assert_eq!(translate(b"ATGGCCTAA", 1), b"WP".to_vec());
+        assert_eq!(translate(b"atggcc", 0), b"MA".to_vec());
+        assert_eq!(translate(b"AUGUUU", 0), b"MF".to_vec());
+        assert_eq!(translate(b"ATGNCCTA", 0), b"MX".to_vec());
+        assert_eq!(translate(b"AT", 0), b"".to_vec());
+        assert_eq!(translate(b"AT", 5), b"".to_vec());
+    }
+
+    #[test]
+    fn translate_six_frames() {
+        let frames = super::translate_six_frames(b"ATGAAATTTGGG");
+
+        assert_eq!(frames[0], b"MKFG".to_vec());
+        assert_eq!(frames[3], b"PKFH".to_vec());
+        assert_eq!(frames[4], b"PNF".to_vec());
+        assert_eq!(frames.iter().filter(|frame| !frame.is_empty()).count(), 6);
+    }
+
+    #[test]
+    fn codon_table() {
+        assert_eq!(translate(b"TGAAGA", 0), b"*R".to_vec());
+        assert_eq!(
+            translate_with(&CodonTable::VERTEBRATE_MITOCHONDRIAL, b"TGAAGAATA", 0),
+            b"W*M".to_vec()
+        );
+        assert_eq!(CodonTable::default(), CodonTable::STANDARD);
+    }
+
+    #[test]
+    fn homopolymer_compress() {
+        assert_eq!(
+            super::homopolymer_compress(b"AAACGGGTTA"),
+            b"ACGTA".to_vec()
+        );
+        assert_eq!(super::homopolymer_compress(b"AaA"), b"AaA".to_vec());
+        assert_eq!(super::homopolymer_compress(b""), b"".to_vec());
+
+        let mut out = Vec::with_capacity(16);
+        homopolymer_compress_into(b"CCCCCCCCCCCCG", &mut out);
+        assert_eq!(out, b"CG".to_vec());
+        assert!(out.capacity() >= 16);
+
+        assert_eq!(
+            homopolymer_compress_with_lengths(b"AAACGGGTTA"),
+            (b"ACGTA".to_vec(), vec![3, 1, 3, 2, 1])
+        );
+        assert_eq!(
+            homopolymer_compress_with_lengths(b""),
+            (Vec::new(), Vec::new())
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! Accumulators of statistics on records, they could be fill by any parser.
+
+/* project use */
+use crate::block;
+use crate::error;
+use crate::fastq;
+use crate::fastx;
+
+/// Accumulator of sequence lengths, compute N50, L50, min, max and mean length
+///
+/// Lengths are store in a histogram, with a bucket width of 1 statistics are exact. A larger bucket width reduce memory usage on reads with many distinct lengths, N50 is then the lower bound of a bucket and L50 is estimate with mean length of this bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthStats {
+    bucket: u64,
+    // bucket index -> (number of sequences, sum of lengths)
+    histogram: std::collections::BTreeMap<u64, (u64, u64)>,
+    count: u64,
+    total: u64,
+    min: u64,
+    max: u64,
+}
+
+/// Statistics computed by [LengthStats::finalize], all fields are 0 if no length was added
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthSummary {
+    /// Number of sequences
+    pub count: u64,
+    /// Sum of lengths
+    pub total: u64,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    /// Length such that sequences of this length or longer contains half of total length
+    pub n50: u64,
+    /// Number of sequences of length greater or equal to n50 needed to reach half of total length
+    pub l50: u64,
+}
+
+impl LengthStats {
+    /// Build an accumulator with exact statistics
+    pub fn new() -> Self {
+        Self::with_bucket(1)
+    }
+
+    /// Build an accumulator with a histogram of bucket width, width is at least 1
+    pub fn with_bucket(bucket: u64) -> Self {
+        Self {
+            bucket: bucket.max(1),
+            histogram: std::collections::BTreeMap::new(),
+            count: 0,
+            total: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// Add a sequence length
+    pub fn update(&mut self, length: u64) {
+        let bucket = self.histogram.entry(length / self.bucket).or_insert((0, 0));
+        bucket.0 += 1;
+        bucket.1 += length;
+
+        self.count += 1;
+        self.total += length;
+        self.min = self.min.min(length);
+        self.max = self.max.max(length);
+    }
+
+    /// Add lengths of other accumulator, used to merge accumulator of each thread, other must have same bucket width
+    pub fn merge(&mut self, other: &Self) {
+        debug_assert_eq!(self.bucket, other.bucket);
+
+        for (key, (count, sum)) in other.histogram.iter() {
+            let bucket = self.histogram.entry(*key).or_insert((0, 0));
+            bucket.0 += count;
+            bucket.1 += sum;
+        }
+
+        self.count += other.count;
+        self.total += other.total;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Number of sequences added
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Compute statistics of lengths added
+    pub fn finalize(&self) -> LengthSummary {
+        if self.count == 0 {
+            return LengthSummary {
+                count: 0,
+                total: 0,
+                min: 0,
+                max: 0,
+                mean: 0.0,
+                n50: 0,
+                l50: 0,
+            };
+        }
+
+        let mut n50 = 0;
+        let mut l50 = 0;
+        let mut cumulative = 0;
+
+        // longest sequences first, stop when half of total length is reached
+        for (key, (count, sum)) in self.histogram.iter().rev() {
+            if (cumulative + sum) * 2 < self.total {
+                cumulative += sum;
+                l50 += count;
+                continue;
+            }
+
+            let mean = (sum / count).max(1);
+            let missing = (self.total + 1) / 2 - cumulative;
+            l50 += ((missing + mean - 1) / mean).clamp(1, *count);
+            n50 = key * self.bucket;
+            break;
+        }
+
+        LengthSummary {
+            count: self.count,
+            total: self.total,
+            min: self.min,
+            max: self.max,
+            mean: self.total as f64 / self.count as f64,
+            n50,
+            l50,
+        }
+    }
+}
+
+impl Default for LengthStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+crate::fastx_sequential!(
+    LengthParser,
+    LengthStats,
+    |record: fastx::FastxRecord, stats: &mut LengthStats| {
+        stats.update(record.record().sequence.len() as u64)
+    }
+);
+
+/// Compute length statistics of sequences of a fasta or fastq file, records are read sequentially
+pub fn length_stats<P>(path: P) -> error::Result<LengthSummary>
+where
+    P: AsRef<std::path::Path>,
+{
+    let mut stats = LengthStats::new();
+
+    LengthParser::new().parse(path, &mut stats)?;
+
+    Ok(stats.finalize())
+}
+
+/// Number of quality values count by [QualityProfile], higher qualities are count as the last one
+pub const QUALITY_SPACE: usize = 94;
+
+/// Counts of bases and qualities at one position of reads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionCounts {
+    /// A: 0, C: 1, G: 2, T: 3, other: 4
+    pub bases: [u64; 5],
+    /// Number of bases for each quality value, after phred offset is removed
+    pub qualities: [u64; QUALITY_SPACE],
+}
+
+impl PositionCounts {
+    pub fn new() -> Self {
+        Self {
+            bases: [0; 5],
+            qualities: [0; QUALITY_SPACE],
+        }
+    }
+
+    /// Add counts of other position
+    pub fn merge(&mut self, other: &Self) {
+        for (count, other) in self.bases.iter_mut().zip(other.bases.iter()) {
+            *count += other;
+        }
+
+        for (count, other) in self.qualities.iter_mut().zip(other.qualities.iter()) {
+            *count += other;
+        }
+    }
+
+    /// Number of bases at this position
+    pub fn nb_bases(&self) -> u64 {
+        self.bases.iter().sum()
+    }
+
+    /// Mean quality of bases
+    pub fn mean_quality(&self) -> f64 {
+        let sum: u64 = self
+            .qualities
+            .iter()
+            .enumerate()
+            .map(|(quality, count)| quality as u64 * count)
+            .sum();
+
+        sum as f64 / self.nb_bases() as f64
+    }
+
+    /// Quality of mean error probability of bases, qualities lower than 4 are count as an error probability of 0.5
+    pub fn error_quality(&self) -> f64 {
+        let error: f64 = self
+            .qualities
+            .iter()
+            .enumerate()
+            .map(|(quality, count)| {
+                let probability = if quality < 4 {
+                    0.5
+                } else {
+                    10_f64.powf(-(quality as f64) / 10.0)
+                };
+                probability * *count as f64
+            })
+            .sum();
+
+        -4.343 * ((error + 1e-6) / (self.nb_bases() as f64 + 1e-6)).ln()
+    }
+
+    /// Number of bases with a quality lower than threshold
+    pub fn nb_low_quality(&self, threshold: u8) -> u64 {
+        self.qualities[..(threshold as usize).min(QUALITY_SPACE)]
+            .iter()
+            .sum()
+    }
+}
+
+impl Default for PositionCounts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accumulator of per position bases and qualities of fastq records, like seqtk fqchk
+///
+/// Each thread could fill its own profile, profiles are merged at end with [QualityProfile::merge].
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityProfile {
+    threshold: u8,
+    phred_offset: u8,
+    lengths: LengthStats,
+    positions: Vec<PositionCounts>,
+}
+
+impl QualityProfile {
+    /// Quality lower than threshold are low quality
+    pub fn new(threshold: u8, phred_offset: u8) -> Self {
+        Self {
+            threshold,
+            phred_offset,
+            lengths: LengthStats::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    /// Add bases and qualities of record
+    pub fn update(&mut self, record: &block::Record) {
+        self.lengths.update(record.sequence.len() as u64);
+
+        if record.sequence.len() > self.positions.len() {
+            self.positions
+                .resize(record.sequence.len(), PositionCounts::new());
+        }
+
+        for ((position, nuc), qual) in self
+            .positions
+            .iter_mut()
+            .zip(record.sequence)
+            .zip(record.quality_scores(self.phred_offset))
+        {
+            position.bases[block::BASE_INDEX[*nuc as usize] as usize] += 1;
+            position.qualities[(qual as usize).min(QUALITY_SPACE - 1)] += 1;
+        }
+    }
+
+    /// Add counts of other profile, other must have same threshold and phred offset
+    pub fn merge(&mut self, other: &Self) {
+        debug_assert_eq!(
+            (self.threshold, self.phred_offset),
+            (other.threshold, other.phred_offset)
+        );
+
+        self.lengths.merge(&other.lengths);
+
+        if other.positions.len() > self.positions.len() {
+            self.positions
+                .resize(other.positions.len(), PositionCounts::new());
+        }
+
+        for (position, other) in self.positions.iter_mut().zip(other.positions.iter()) {
+            position.merge(other);
+        }
+    }
+
+    /// Statistics of length of records
+    pub fn lengths(&self) -> LengthSummary {
+        self.lengths.finalize()
+    }
+
+    /// Counts of each position
+    pub fn positions(&self) -> &[PositionCounts] {
+        &self.positions
+    }
+
+    /// Sum of counts of all positions
+    pub fn all(&self) -> PositionCounts {
+        let mut all = PositionCounts::new();
+        for position in self.positions.iter() {
+            all.merge(position);
+        }
+
+        all
+    }
+
+    fn write_position(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        position: &PositionCounts,
+    ) -> std::fmt::Result {
+        let nb_bases = position.nb_bases();
+        let percent = |count: u64| 100.0 * count as f64 / nb_bases as f64;
+        let low = position.nb_low_quality(self.threshold);
+
+        write!(f, "{}", nb_bases)?;
+        for count in position.bases.iter() {
+            write!(f, "\t{:.1}", percent(*count))?;
+        }
+        write!(
+            f,
+            "\t{:.1}\t{:.1}\t{:.1}\t{:.1}",
+            position.mean_quality(),
+            position.error_quality(),
+            percent(low),
+            percent(nb_bases - low)
+        )
+    }
+}
+
+impl std::fmt::Display for QualityProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lengths = self.lengths();
+
+        std::writeln!(
+            f,
+            "min_len: {}; max_len {}; avg_len {:.2}",
+            lengths.min,
+            lengths.max,
+            lengths.mean
+        )?;
+        std::writeln!(
+            f,
+            "POS\t#bases\t%A\t%C\t%G\t%T\t%N\tavgQ\terrQ\t%low\t%high"
+        )?;
+
+        std::write!(f, "ALL\t")?;
+        self.write_position(f, &self.all())?;
+        std::writeln!(f)?;
+
+        for (index, position) in self.positions.iter().enumerate() {
+            std::write!(f, "{}\t", index + 1)?;
+            self.write_position(f, position)?;
+            std::writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+crate::fastq_sequential!(
+    QualityParser,
+    QualityProfile,
+    |record: block::Record, profile: &mut QualityProfile| profile.update(&record)
+);
+
+/// Compute quality profile of a fastq file, blocks are read in parallel and profile of each thread are merged
+///
+/// Result is equal to the one of [QualityParser], run it in [ThreadPool::install](rayon::ThreadPool::install) to choose thread pool.
+pub fn quality_profile<P>(path: P, threshold: u8, phred_offset: u8) -> error::Result<QualityProfile>
+where
+    P: AsRef<std::path::Path>,
+{
+    quality_profile_from(fastq::Producer::new(path)?, threshold, phred_offset)
+}
+
+/// Same as [quality_profile] with a chosen blocksize
+pub fn quality_profile_with_blocksize<P>(
+    blocksize: u64,
+    path: P,
+    threshold: u8,
+    phred_offset: u8,
+) -> error::Result<QualityProfile>
+where
+    P: AsRef<std::path::Path>,
+{
+    quality_profile_from(
+        fastq::Producer::with_blocksize(blocksize, path)?,
+        threshold,
+        phred_offset,
+    )
+}
+
+fn quality_profile_from(
+    producer: fastq::Producer,
+    threshold: u8,
+    phred_offset: u8,
+) -> error::Result<QualityProfile> {
+    crate::parser::fold::fold(
+        producer,
+        fastq::Reader::read_record,
+        || QualityProfile::new(threshold, phred_offset),
+        |profile: &mut QualityProfile, record: block::Record| profile.update(&record),
+        |mut profile, other| {
+            profile.merge(&other);
+            profile
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_stats() {
+        let mut stats = LengthStats::new();
+        for length in [2, 3, 4, 5, 6, 7, 8, 9, 10] {
+            stats.update(length);
+        }
+
+        let summary = stats.finalize();
+        assert_eq!(summary.count, 9);
+        assert_eq!(summary.total, 54);
+        assert_eq!((summary.min, summary.max), (2, 10));
+        assert!((summary.mean - 6.0).abs() < f64::EPSILON);
+        // 10 + 9 + 8 = 27
+        assert_eq!((summary.n50, summary.l50), (8, 3));
+
+        let mut other = LengthStats::new();
+        other.update(100);
+        stats.merge(&other);
+        assert_eq!((stats.finalize().n50, stats.finalize().l50), (100, 1));
+
+        assert_eq!(LengthStats::new().finalize().n50, 0);
+    }
+
+    #[test]
+    fn bucket() {
+        let mut exact = LengthStats::new();
+        let mut bucket = LengthStats::with_bucket(100);
+        for length in (1..=1_000).map(|i| i * 7) {
+            exact.update(length);
+            bucket.update(length);
+        }
+
+        let exact = exact.finalize();
+        let bucket = bucket.finalize();
+        assert_eq!(
+            (bucket.count, bucket.total, bucket.min, bucket.max),
+            (exact.count, exact.total, exact.min, exact.max)
+        );
+        assert!(bucket.n50 <= exact.n50 && exact.n50 < bucket.n50 + 100);
+        assert!((bucket.l50 as i64 - exact.l50 as i64).abs() <= 15);
+    }
+
+    #[test]
+    fn quality_profile() {
+        let mut profile = QualityProfile::new(20, 33);
+        profile.update(&block::Record {
+            comment: b"@1",
+            sequence: b"ACGT",
+            plus: b"+",
+            quality: b"+5+5",
+        });
+        profile.update(&block::Record {
+            comment: b"@2",
+            sequence: b"AN",
+            plus: b"+",
+            quality: b"5~",
+        });
+
+        assert_eq!(profile.positions().len(), 4);
+        assert_eq!(profile.positions()[0].bases, [2, 0, 0, 0, 0]);
+        assert_eq!(profile.positions()[1].bases, [0, 1, 0, 0, 1]);
+        assert_eq!(profile.positions()[1].qualities[20], 1);
+        assert_eq!(profile.positions()[1].qualities[QUALITY_SPACE - 1], 1);
+        assert!((profile.positions()[0].mean_quality() - 15.0).abs() < 1e-9);
+        assert_eq!(profile.positions()[0].nb_low_quality(20), 1);
+
+        let all = profile.all();
+        assert_eq!(all.nb_bases(), 6);
+        assert_eq!(all.bases, [2, 1, 1, 1, 1]);
+
+        let output = profile.to_string();
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "min_len: 2; max_len 4; avg_len 3.00");
+        assert!(lines[2].starts_with("ALL\t6\t33.3\t16.7\t16.7\t16.7\t16.7\t"));
+        assert!(lines[3].starts_with("1\t2\t100.0\t0.0"));
+    }
+
+    #[test]
+    fn quality_profile_parallel() {
+        let file = crate::tests::generate_fastq(42, 1_000, 150);
+
+        let mut sequential = QualityProfile::new(20, 33);
+        QualityParser::new()
+            .with_blocksize(10_000, file.path(), &mut sequential)
+            .unwrap();
+
+        let parallel = super::quality_profile(file.path(), 20, 33).unwrap();
+        assert_eq!(parallel, sequential);
+        assert_eq!(parallel.to_string(), sequential.to_string());
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(3)
+            .build()
+            .unwrap();
+        assert_eq!(
+            pool.install(|| super::quality_profile_with_blocksize(10_000, file.path(), 20, 33))
+                .unwrap(),
+            sequential
+        );
+        assert_eq!(parallel.all().nb_bases(), 150_000);
+        assert_eq!(parallel.lengths().count, 1_000);
+    }
+
+    #[test]
+    fn parser() {
+        let summary = super::length_stats(crate::tests::generate_fasta(42, 100, 150)).unwrap();
+
+        assert_eq!(summary.count, 100);
+        assert_eq!((summary.min, summary.max, summary.n50), (150, 150, 150));
+        assert_eq!(summary.l50, 50);
+
+        let mut stats = LengthStats::new();
+        LengthParser::new()
+            .parse(crate::tests::generate_fastq(42, 10, 50), &mut stats)
+            .unwrap();
+        assert_eq!(stats.count(), 10);
+    }
+}
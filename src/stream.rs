@@ -0,0 +1,312 @@
+//! Read fasta or fastq records from any [Read](std::io::Read), like stdin or a pipe, that can't be map on memory.
+//!
+//! Data is read sequentially and split in blocks that end on a record boundary, the partial record at end of a chunk is keep at begin of next block. Offset of blocks and errors are offset in read data.
+
+/* std use */
+use std::io::Read;
+
+/* project use */
+use crate::block;
+use crate::error;
+use crate::fasta;
+use crate::fastq;
+use crate::fastx;
+
+/// A Block producer that read a stream, blocks could only be produce sequentially
+pub struct Producer {
+    reader: Box<dyn Read + Send>,
+    format: fastx::Format,
+    blocksize: usize,
+    pending: Vec<u8>,
+    offset: u64,
+    eof: bool,
+}
+
+impl Producer {
+    /// Create a stream Block producer, format of records is detected on the first non-whitespace byte, an empty stream is read as fasta
+    ///
+    /// A [UTF-8 byte order mark](block::UTF8_BOM) at begin of stream is skipped.
+    pub fn new<R>(blocksize: u64, reader: R) -> error::Result<Self>
+    where
+        R: Read + Send + 'static,
+    {
+        let mut producer = Self::with_format(fastx::Format::Fasta, blocksize, reader);
+
+        producer.fill()?;
+        if producer.pending.starts_with(&block::UTF8_BOM) {
+            producer.pending.drain(..block::UTF8_BOM.len());
+            producer.offset = block::UTF8_BOM.len() as u64;
+        }
+        if let Some(byte) = producer.pending.iter().find(|c| !c.is_ascii_whitespace()) {
+            producer.format = fastx::Format::from_byte(*byte)?;
+        }
+
+        Ok(producer)
+    }
+
+    /// Create a stream Block producer of records in format, nothing is read before first call of next_block
+    pub fn with_format<R>(format: fastx::Format, blocksize: u64, reader: R) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        Self {
+            reader: Box::new(reader),
+            format,
+            blocksize: blocksize.max(1) as usize,
+            pending: Vec::new(),
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// Get format of records
+    pub fn format(&self) -> fastx::Format {
+        self.format
+    }
+
+    /// Get blocksize
+    pub fn blocksize(&self) -> u64 {
+        self.blocksize as u64
+    }
+
+    /// Get number of bytes already send in blocks
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+        self.fill()?;
+
+        if self.pending.iter().all(|c| *c == b'\n' || *c == b'\r') {
+            // end of stream contains only empty lines, there is no more record
+            self.offset += self.pending.len() as u64;
+            self.pending.clear();
+            return Ok(None);
+        }
+
+        let length = if self.eof {
+            if let Some(pos) = self.truncated_record() {
+                return Err(error::Error::TruncatedFile {
+                    offset: self.offset + pos as u64,
+                });
+            }
+
+            self.pending.len()
+        } else {
+            match self.correct_block_size(&self.pending) {
+                Ok(0) | Err(error::Error::NoNewLineInBlock { .. }) => {
+                    return Err(self.blocksize_too_small())
+                }
+                Ok(length) => length as usize,
+                Err(e) => return Err(e.add_offset(self.offset)),
+            }
+        };
+
+        // partial record at end of chunk is the begin of next block
+        let rest = self.pending.split_off(length);
+        let data = std::mem::replace(&mut self.pending, rest);
+
+        let block = block::Block::from_vec(self.offset, data);
+        self.offset += length as u64;
+
+        Ok(Some(block))
+    }
+
+    /// Read data until pending data contains blocksize bytes or stream end
+    fn fill(&mut self) -> error::Result<()> {
+        self.fill_to(self.blocksize)
+    }
+
+    /// Read data until pending data contains target bytes or stream end
+    fn fill_to(&mut self, target: usize) -> error::Result<()> {
+        while !self.eof && self.pending.len() < target {
+            let length = self.pending.len();
+            self.pending.resize(target, 0);
+
+            match self.reader.read(&mut self.pending[length..]) {
+                Ok(0) => {
+                    self.pending.truncate(length);
+                    self.eof = true;
+                }
+                Ok(nb_read) => self.pending.truncate(length + nb_read),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                    self.pending.truncate(length)
+                }
+                Err(source) => {
+                    self.pending.truncate(length);
+                    return Err(error::Error::ReadFile { source });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build error for pending data that didn't contain a complete record
+    ///
+    /// Pending data is grow by doubling window until a record boundary is found, to report a blocksize large enough.
+    fn blocksize_too_small(&mut self) -> error::Error {
+        let mut length = self.blocksize;
+
+        loop {
+            length = length.saturating_mul(2);
+            if let Err(e) = self.fill_to(length) {
+                return e;
+            }
+
+            if self.pending.len() < length {
+                return error::Error::BlocksizeTooSmall {
+                    blocksize: self.blocksize as u64,
+                    min_needed: self.pending.len() as u64,
+                };
+            }
+
+            if let Ok(blocksize) = self.correct_block_size(&self.pending[..length]) {
+                if blocksize != 0 {
+                    return error::Error::BlocksizeTooSmall {
+                        blocksize: self.blocksize as u64,
+                        min_needed: length as u64,
+                    };
+                }
+            }
+        }
+    }
+
+    fn correct_block_size(&self, data: &[u8]) -> error::Result<u64> {
+        match self.format {
+            fastx::Format::Fasta => fasta::Producer::correct_block_size(data),
+            fastx::Format::Fastq => fastq::Producer::correct_block_size(data),
+        }
+    }
+
+    fn truncated_record(&self) -> Option<usize> {
+        match self.format {
+            fastx::Format::Fasta => fasta::Producer::truncated_record(&self.pending),
+            fastx::Format::Fastq => fastq::Producer::truncated_record(&self.pending),
+        }
+    }
+}
+
+impl Iterator for Producer {
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reader that return at most 7 bytes by read, like a slow pipe
+    struct Pipe(std::io::Cursor<Vec<u8>>);
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let length = buf.len().min(7);
+            self.0.read(&mut buf[..length])
+        }
+    }
+
+    #[test]
+    fn fastq() {
+        let fastq = crate::tests::generate_fastq(42, 500, 150);
+        let data = std::fs::read(fastq.path()).unwrap();
+
+        let mut expected = Vec::new();
+        fastq::for_each(fastq.path(), |record| {
+            expected.push((record.comment.to_vec(), record.quality.to_vec()))
+        })
+        .unwrap();
+
+        let mut producer = Producer::new(1_000, Pipe(std::io::Cursor::new(data.clone()))).unwrap();
+        assert_eq!(producer.format(), fastx::Format::Fastq);
+
+        let mut records = Vec::new();
+        let mut nb_block = 0;
+        while let Some(block) = producer.next_block().unwrap() {
+            assert!(block.len() <= 1_000);
+            nb_block += 1;
+
+            fastq::Reader::new_validated(block)
+                .for_each(|record| records.push((record.comment.to_vec(), record.quality.to_vec())))
+                .unwrap();
+        }
+
+        assert!(nb_block > 1);
+        assert_eq!(records, expected);
+        assert_eq!(producer.offset(), data.len() as u64);
+    }
+
+    #[test]
+    fn fasta() {
+        let producer = fasta::from_reader(std::io::Cursor::new(b">1\nACGT\n>2\nTG\n".to_vec()));
+        assert_eq!(producer.format(), fastx::Format::Fasta);
+
+        let mut sequences = Vec::new();
+        for block in producer {
+            fasta::Reader::new(block.unwrap())
+                .for_each(|record| sequences.push(record.sequence.to_vec()))
+                .unwrap();
+        }
+
+        assert_eq!(sequences, vec![b"ACGT".to_vec(), b"TG".to_vec()]);
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let mut data = block::UTF8_BOM.to_vec();
+        data.extend_from_slice(b"@1\nACGT\n+\n!!!!\n");
+
+        let mut producer = Producer::new(1_000, std::io::Cursor::new(data)).unwrap();
+        assert_eq!(producer.format(), fastx::Format::Fastq);
+
+        let block = producer.next_block().unwrap().unwrap();
+        assert_eq!(block.file_offset(), 3);
+        assert_eq!(block.records_fastq().count(), 1);
+        assert_eq!(producer.offset(), 18);
+    }
+
+    #[test]
+    fn errors() {
+        let mut producer = Producer::new(1_000, std::io::Cursor::new(b"".to_vec())).unwrap();
+        assert!(producer.next_block().unwrap().is_none());
+
+        let mut producer = fastq::from_reader(std::io::Cursor::new(b"@1\nACGT\n+\n!!".to_vec()));
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::TruncatedFile { offset: 0 })
+        ));
+
+        let mut producer = Producer::with_format(
+            fastx::Format::Fastq,
+            10,
+            std::io::Cursor::new(b"@1\nACGTACGT\n+\n!!!!!!!!\n".to_vec()),
+        );
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::BlocksizeTooSmall {
+                blocksize: 10,
+                min_needed: 23
+            })
+        ));
+
+        let mut producer = Producer::with_format(
+            fastx::Format::Fastq,
+            10,
+            std::io::Cursor::new(
+                b"@1\nACGTACGT\n+\n!!!!!!!!\n@2\nACGTACGTACGT\n+\n!!!!!!!!!!!!\n".to_vec(),
+            ),
+        );
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::BlocksizeTooSmall {
+                blocksize: 10,
+                min_needed: 40
+            })
+        ));
+
+        assert!(Producer::new(1_000, std::io::Cursor::new(b"ACGT".to_vec())).is_err());
+    }
+}
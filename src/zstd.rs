@@ -0,0 +1,149 @@
+//! Read zstd compressed fasta or fastq file, only available with feature `zstd`.
+//!
+//! Compressed file can't be map on memory, data is decompressed and read as a [stream](crate::stream). Offset of blocks and errors are offset in decompressed data.
+
+/* project use */
+use crate::block;
+use crate::error;
+use crate::fastx;
+use crate::stream;
+
+/// A Block producer that decompress a zstd stream, format of records is detected on the first non-whitespace byte
+pub struct Producer {
+    stream: stream::Producer,
+}
+
+impl Producer {
+    /// Create a zstd Block producer, blocksize is [DEFAULT_BLOCKSIZE](crate::DEFAULT_BLOCKSIZE)
+    pub fn new<P>(path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Self::with_blocksize(crate::DEFAULT_BLOCKSIZE, path)
+    }
+
+    pub fn with_blocksize<P>(blocksize: u64, path: P) -> error::Result<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let file = std::fs::File::open(path).map_err(|source| error::Error::OpenFile { source })?;
+
+        Self::from_reader(blocksize, file)
+    }
+
+    /// Create a zstd Block producer that decompress data read in reader
+    pub fn from_reader<R>(blocksize: u64, reader: R) -> error::Result<Self>
+    where
+        R: std::io::Read + Send + 'static,
+    {
+        let decoder = ::zstd::stream::read::Decoder::new(reader)
+            .map_err(|source| error::Error::ReadFile { source })?;
+
+        Ok(Self {
+            stream: stream::Producer::new(blocksize, decoder)?,
+        })
+    }
+
+    /// Get format of records
+    pub fn format(&self) -> fastx::Format {
+        self.stream.format()
+    }
+
+    /// Get blocksize
+    pub fn blocksize(&self) -> u64 {
+        self.stream.blocksize()
+    }
+
+    /// Get number of decompressed bytes already send in blocks
+    pub fn offset(&self) -> u64 {
+        self.stream.offset()
+    }
+
+    pub fn next_block(&mut self) -> error::Result<Option<block::Block>> {
+        self.stream.next_block()
+    }
+}
+
+impl Iterator for Producer {
+    type Item = error::Result<block::Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_block().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fastq;
+
+    fn compress(path: &std::path::Path) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        ::zstd::stream::copy_encode(
+            std::fs::File::open(path).unwrap(),
+            file.reopen().unwrap(),
+            3,
+        )
+        .unwrap();
+
+        file
+    }
+
+    #[test]
+    fn round_trip() {
+        let fastq = crate::tests::generate_fastq(42, 1_000, 150);
+        let compressed = compress(fastq.path());
+
+        let mut expected = Vec::new();
+        fastq::for_each(fastq.path(), |record| {
+            expected.push((record.comment.to_vec(), record.quality.to_vec()))
+        })
+        .unwrap();
+
+        let mut producer = Producer::with_blocksize(1_000, compressed.path()).unwrap();
+        assert_eq!(producer.format(), fastx::Format::Fastq);
+
+        let mut records = Vec::new();
+        let mut nb_block = 0;
+        while let Some(block) = producer.next_block().unwrap() {
+            assert!(block.len() <= 1_000);
+            nb_block += 1;
+
+            fastq::Reader::new_validated(block)
+                .for_each(|record| records.push((record.comment.to_vec(), record.quality.to_vec())))
+                .unwrap();
+        }
+
+        assert!(nb_block > 1);
+        assert_eq!(records, expected);
+        assert_eq!(
+            producer.offset(),
+            std::fs::metadata(fastq.path()).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn fastx_open() {
+        let compressed = compress(crate::tests::generate_fasta(42, 100, 150).path());
+
+        let (format, producer) = fastx::open(compressed.path()).unwrap();
+        assert_eq!(format, fastx::Format::Fasta);
+        assert_eq!(producer.count(), 1);
+    }
+
+    #[test]
+    fn blocksize_too_small() {
+        let compressed = compress(crate::tests::generate_fastq(42, 10, 150).path());
+
+        let mut producer = Producer::with_blocksize(100, compressed.path()).unwrap();
+        assert!(matches!(
+            producer.next_block(),
+            Err(error::Error::BlocksizeTooSmall {
+                blocksize: 100,
+                min_needed: 400
+            })
+        ));
+    }
+}